@@ -1,5 +1,7 @@
-use criterion::{criterion_group, criterion_main, Criterion};
-use ipi::value::hash::Hash;
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use ipi::value::hash::{Hash, Hasher, ParallelHasher};
 
 fn test_hash(data: &[u8], expected_cid: &str) {
     // hash with Hash::with_bytes
@@ -35,11 +37,87 @@ fn hash_dag_level_2_entry() {
     test_hash(data, expected_cid);
 }
 
+fn hasher_dag_level_2_entry() {
+    let data = &[0; 262_144 * 174 + 1];
+    let expected_cid = "bafybeihqwzd3o6q6v3pmwhzjy22vokhr767burokmqemg63hptx2nqd7ym";
+
+    // hash with Hasher
+    let mut hasher = Hasher::default();
+    for chunk in data.chunks(1_048_576) {
+        hasher.update(chunk);
+    }
+    let hash = hasher.finalize().to_string();
+    assert_eq!(hash, expected_cid);
+}
+
+fn parallel_hasher_dag_level_2_entry() {
+    let data = &[0; 262_144 * 174 + 1];
+    let expected_cid = "bafybeihqwzd3o6q6v3pmwhzjy22vokhr767burokmqemg63hptx2nqd7ym";
+
+    // hash with ParallelHasher
+    let mut hasher = ParallelHasher::default();
+    for chunk in data.chunks(1_048_576) {
+        hasher.update(chunk);
+    }
+    let hash = hasher.finalize().to_string();
+    assert_eq!(hash, expected_cid);
+}
+
+fn hash_file_dag_level_2_entry(path: &Path) {
+    let expected_cid = "bafybeihqwzd3o6q6v3pmwhzjy22vokhr767burokmqemg63hptx2nqd7ym";
+
+    // hash with Hash::from_path
+    let hash = Hash::from_path(path).unwrap().to_string();
+    assert_eq!(hash, expected_cid);
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("hash_small", |b| b.iter(hash_small));
     c.bench_function("hash_chunk", |b| b.iter(hash_chunk));
     c.bench_function("hash_dag_level_1", |b| b.iter(hash_dag_level_1));
     c.bench_function("hash_dag_level_2_entry", |b| b.iter(hash_dag_level_2_entry));
+    c.bench_function("hasher_dag_level_2_entry", |b| {
+        b.iter(hasher_dag_level_2_entry)
+    });
+    c.bench_function("parallel_hasher_dag_level_2_entry", |b| {
+        b.iter(parallel_hasher_dag_level_2_entry)
+    });
+
+    // store the data
+    let path = ::std::env::temp_dir().join(format!("ipi-bench-hash-{}", ::std::process::id()));
+    ::std::fs::write(&path, vec![0u8; 262_144 * 174 + 1]).unwrap();
+
+    c.bench_function("hash_file_dag_level_2_entry", |b| {
+        b.iter(|| hash_file_dag_level_2_entry(&path))
+    });
+    ::std::fs::remove_file(&path).unwrap();
+
+    // compare the streaming hashers over the large input, read by 1 MiB
+    let data: Vec<u8> = (0..1 << 28).map(|index: u32| (index % 251) as u8).collect();
+    let expected_hash = Hash::with_bytes(&data);
+
+    let mut group = c.benchmark_group("hash_256mib");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("hasher", |b| {
+        b.iter(|| {
+            let mut hasher = Hasher::default();
+            for chunk in data.chunks(1_048_576) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), expected_hash);
+        })
+    });
+    group.bench_function("parallel_hasher", |b| {
+        b.iter(|| {
+            let mut hasher = ParallelHasher::default();
+            for chunk in data.chunks(1_048_576) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), expected_hash);
+        })
+    });
+    group.finish();
 }
 
 criterion_group!(benches_bash, criterion_benchmark);
@@ -0,0 +1,62 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ipi::{
+    account::{Account, AccountRef},
+    sealed::{Commitment, Sealed},
+};
+
+/// The size of each wrapped key: the recipient, the ephemeral key, the key and the tag
+const RECIPIENT_OVERHEAD: usize = 32 + 32 + 32 + 16;
+
+fn seal(data: &[u8], recipients: &[AccountRef]) -> Sealed {
+    Sealed::seal_archived(data, recipients, Commitment::Ciphertext).unwrap()
+}
+
+fn test_size(data: &[u8], recipients: &[AccountRef]) {
+    let sealed = seal(data, recipients);
+
+    // the body is encrypted once, regardless of the recipients
+    assert_eq!(sealed.ciphertext.len(), data.len() + 16);
+    assert_eq!(
+        ::rkyv::to_bytes::<_, 64>(&sealed).unwrap().len()
+            - ::rkyv::to_bytes::<_, 64>(&seal(data, &recipients[..1]))
+                .unwrap()
+                .len(),
+        RECIPIENT_OVERHEAD * (recipients.len() - 1),
+    );
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let data = &[0; 262_144];
+    let accounts: Vec<_> = (0..100).map(|_| Account::generate()).collect();
+    let recipients: Vec<_> = accounts.iter().map(Account::account_ref).collect();
+
+    for count in [1, 10, 100] {
+        let recipients = &recipients[..count];
+        test_size(data, recipients);
+
+        c.bench_function(&format!("seal_chunk_recipients_{count}"), |b| {
+            b.iter(|| seal(data, recipients))
+        });
+
+        let sealed = seal(data, recipients);
+        let account = &accounts[count - 1];
+        c.bench_function(&format!("open_chunk_recipients_{count}"), |b| {
+            b.iter(|| sealed.open_archived(account).unwrap())
+        });
+    }
+
+    // wrap the key again, without re-encrypting the body
+    let sealed = seal(data, &recipients[..1]);
+    c.bench_function("add_recipients_99", |b| {
+        b.iter(|| {
+            let mut sealed = sealed.clone();
+            sealed
+                .add_recipients(&accounts[0], &recipients[1..])
+                .unwrap();
+            sealed
+        })
+    });
+}
+
+criterion_group!(benches_sealed, criterion_benchmark);
+criterion_main!(benches_sealed);
@@ -3,6 +3,7 @@ use bytecheck::CheckBytes;
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::{
+    ipld::ToIpld,
     metadata::Metadata,
     signature::{Keypair, PublicKey, Signature, SignatureSerializer},
 };
@@ -37,6 +38,12 @@ impl ::core::ops::Deref for GuarantorSigned {
     }
 }
 
+impl AsRef<Metadata> for GuarantorSigned {
+    fn as_ref(&self) -> &Metadata {
+        &self.data.data
+    }
+}
+
 impl Signer<GuaranteeSigned> for GuarantorSigned {
     fn sign(account: &Account, data: GuaranteeSigned) -> Result<Self>
     where
@@ -62,6 +69,29 @@ impl Verifier for GuarantorSigned {
         self.guarantor.verify(&self.data)?;
         self.data.verify(guarantor)
     }
+
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        if self.guarantor.account != self.data.data.guarantor {
+            bail!("guarantor mismatching");
+        }
+
+        self.guarantor.verify_dag_cbor(&self.data)?;
+        self.data.verify_dag_cbor(guarantor)
+    }
+}
+
+impl GuarantorSigned {
+    /// Signs over the DAG-CBOR bytes instead of the archived ones.
+    pub fn sign_dag_cbor(account: &Account, data: GuaranteeSigned) -> Result<Self> {
+        if account.account_ref() != data.guarantor {
+            bail!("guarantor mismatching");
+        }
+
+        Ok(GuarantorSigned {
+            guarantor: account.sign_dag_cbor(&data)?,
+            data,
+        })
+    }
 }
 
 #[derive(
@@ -94,6 +124,12 @@ impl ::core::ops::Deref for GuaranteeSigned {
     }
 }
 
+impl AsRef<Metadata> for GuaranteeSigned {
+    fn as_ref(&self) -> &Metadata {
+        &self.data
+    }
+}
+
 impl Signer<Metadata> for GuaranteeSigned {
     fn sign(account: &Account, data: Metadata) -> Result<Self>
     where
@@ -116,9 +152,27 @@ impl Verifier for GuaranteeSigned {
 
         self.guarantee.verify(&self.data)
     }
+
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        if let Some(guarantor) = guarantor {
+            if &self.data.guarantor != guarantor {
+                bail!("guarantor mismatching");
+            }
+        }
+
+        self.guarantee.verify_dag_cbor(&self.data)
+    }
 }
 
 impl GuaranteeSigned {
+    /// Signs over the DAG-CBOR bytes instead of the archived ones.
+    pub fn sign_dag_cbor(account: &Account, data: Metadata) -> Result<Self> {
+        Ok(Self {
+            guarantee: account.sign_dag_cbor(&data)?,
+            data,
+        })
+    }
+
     pub fn is_self_signed(&self) -> bool {
         self.guarantee.account == self.data.guarantor
     }
@@ -157,6 +211,12 @@ where
 
 pub trait Verifier {
     fn verify(&self, guarantor: Option<&AccountRef>) -> Result<()>;
+
+    /// Verifies the signatures made over the DAG-CBOR bytes.
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        let _ = guarantor;
+        bail!("DAG-CBOR signatures are not supported")
+    }
 }
 
 impl<T> Verifier for &T
@@ -166,6 +226,10 @@ where
     fn verify(&self, guarantor: Option<&AccountRef>) -> Result<()> {
         (**self).verify(guarantor)
     }
+
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        (**self).verify_dag_cbor(guarantor)
+    }
 }
 
 impl<T> Verifier for Box<T>
@@ -175,6 +239,10 @@ where
     fn verify(&self, guarantor: Option<&AccountRef>) -> Result<()> {
         (**self).verify(guarantor)
     }
+
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        (**self).verify_dag_cbor(guarantor)
+    }
 }
 
 impl<T> Verifier for ::core::pin::Pin<T>
@@ -185,6 +253,10 @@ where
     fn verify(&self, guarantor: Option<&AccountRef>) -> Result<()> {
         (**self).verify(guarantor)
     }
+
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        (**self).verify_dag_cbor(guarantor)
+    }
 }
 
 #[derive(
@@ -210,7 +282,7 @@ pub struct Identity {
 }
 
 impl Identity {
-    fn verify<T>(&self, data: &T) -> Result<()>
+    pub(crate) fn verify<T>(&self, data: &T) -> Result<()>
     where
         T: Serialize<SignatureSerializer>,
     {
@@ -218,6 +290,14 @@ impl Identity {
         self.verify_archived(&data)
     }
 
+    fn verify_dag_cbor<T>(&self, data: &T) -> Result<()>
+    where
+        T: ToIpld,
+    {
+        let data = data.to_dag_cbor()?;
+        self.verify_archived(&data)
+    }
+
     fn verify_archived(&self, data: &[u8]) -> Result<()> {
         use ed25519_dalek::Verifier;
 
@@ -316,4 +396,16 @@ impl Account {
             signature: Signature(self.keypair.sign(&::rkyv::to_bytes(data)?)),
         })
     }
+
+    pub(crate) fn sign_dag_cbor<T>(&self, data: &T) -> Result<Identity>
+    where
+        T: ToIpld,
+    {
+        use ed25519_dalek::Signer;
+
+        Ok(Identity {
+            account: self.account_ref(),
+            signature: Signature(self.keypair.sign(&data.to_dag_cbor()?)),
+        })
+    }
 }
@@ -0,0 +1,23 @@
+use anyhow::{anyhow, Result};
+use bytecheck::CheckBytes;
+use rkyv::{
+    de::deserializers::SharedDeserializeMap, validation::validators::DefaultValidator, AlignedVec,
+    Archive, Deserialize,
+};
+
+/// Validates and deserializes the archived bytes, which may not be aligned.
+pub(crate) fn deserialize_unaligned<T>(bytes: &[u8]) -> Result<T>
+where
+    T: Archive,
+    <T as Archive>::Archived:
+        for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+{
+    let mut aligned = AlignedVec::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+
+    let archived = ::rkyv::check_archived_root::<T>(&aligned)
+        .map_err(|e| anyhow!("malformed archived data: {e}"))?;
+    archived
+        .deserialize(&mut SharedDeserializeMap::default())
+        .map_err(|e| anyhow!("failed to deserialize the archived data: {e}"))
+}
@@ -0,0 +1,210 @@
+use anyhow::{anyhow, bail, Result};
+use bytecheck::CheckBytes;
+use rkyv::{Archive, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    account::{Account, AccountRef, GuaranteeSigned, Signer, Verifier},
+    metadata::MetadataBuilder,
+    signature::SignatureSerializer,
+    value::hash::Hash,
+};
+
+/// Domain separation of the leaves and the inner nodes.
+///
+/// Only the prefixes follow RFC 6962: the tree shape does not, as the last odd node
+/// is promoted instead of splitting at the largest power of two,
+/// so the proofs are not compatible with Certificate Transparency logs.
+const PREFIX_LEAF: u8 = 0x00;
+const PREFIX_NODE: u8 = 0x01;
+
+type Digest32 = [u8; 32];
+
+fn hash_leaf(leaf: &Hash) -> Digest32 {
+    Sha256::new()
+        .chain_update([PREFIX_LEAF])
+        .chain_update(Vec::from(*leaf))
+        .finalize()
+        .into()
+}
+
+fn hash_node(left: &Digest32, right: &Digest32) -> Digest32 {
+    Sha256::new()
+        .chain_update([PREFIX_NODE])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// A Merkle tree over the payload hashes, where the last odd node is promoted as is.
+///
+/// A tree always has at least one leaf, as [`MerkleTree::new`] is the only constructor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// The nodes from the leaves to the root
+    levels: Vec<Vec<Digest32>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: &[Hash]) -> Result<Self> {
+        if leaves.is_empty() {
+            bail!("no leaves to build a Merkle tree");
+        }
+
+        let mut levels = vec![leaves.iter().map(hash_leaf).collect::<Vec<_>>()];
+        while let Some(nodes) = levels.last().filter(|nodes| nodes.len() > 1) {
+            let parents = nodes
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node(left, right),
+                    [node] => *node,
+                    _ => unreachable!("chunks of 2"),
+                })
+                .collect();
+            levels.push(parents);
+        }
+        Ok(Self { levels })
+    }
+
+    fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn root(&self) -> Hash {
+        Hash::with_sha2_256_digest(&self.levels[self.levels.len() - 1][0])
+    }
+
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for nodes in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = nodes.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+
+        Some(MerkleProof {
+            index: index as u64,
+            len: self.len() as u64,
+            siblings,
+        })
+    }
+}
+
+/// An inclusion proof of a payload in the batch signed once.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct MerkleProof {
+    pub index: u64,
+    /// The number of the leaves
+    pub len: u64,
+    /// The sibling nodes from the leaf to the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Computes the root of the tree including the payload hash.
+    pub fn root(&self, leaf: &Hash) -> Result<Hash> {
+        if self.index >= self.len {
+            bail!("leaf index out of range: {} >= {}", self.index, self.len);
+        }
+
+        let mut siblings = self.siblings.iter();
+        let mut node = hash_leaf(leaf);
+        let mut position = self.index;
+        let mut len = self.len;
+        while len > 1 {
+            // the last odd node has no sibling
+            if position ^ 1 < len {
+                let sibling = siblings
+                    .next()
+                    .ok_or_else(|| anyhow!("too few Merkle siblings"))?;
+                node = if position & 1 == 0 {
+                    hash_node(&node, sibling)
+                } else {
+                    hash_node(sibling, &node)
+                };
+            }
+            position /= 2;
+            len = len.div_ceil(2);
+        }
+
+        if siblings.next().is_some() {
+            bail!("too many Merkle siblings");
+        }
+        Ok(Hash::with_sha2_256_digest(&node))
+    }
+
+    /// Verifies the payload against the batch root signed as guarantee.
+    pub fn verify<T>(
+        &self,
+        data: &T,
+        root: &GuaranteeSigned,
+        guarantor: Option<&AccountRef>,
+    ) -> Result<()>
+    where
+        T: Serialize<SignatureSerializer>,
+    {
+        let bytes = ::rkyv::to_bytes::<_, 64>(data)?;
+        self.verify_hash(&Hash::with_bytes(&bytes), root, guarantor)
+    }
+
+    pub fn verify_hash(
+        &self,
+        hash: &Hash,
+        root: &GuaranteeSigned,
+        guarantor: Option<&AccountRef>,
+    ) -> Result<()> {
+        if self.root(hash)? != root.hash {
+            bail!("Merkle root mismatching");
+        }
+        root.verify(guarantor)
+    }
+}
+
+impl MetadataBuilder {
+    /// Signs the Merkle root of the payloads once, issuing an inclusion proof to each payload.
+    pub fn build_batch<T>(
+        self,
+        account: &Account,
+        guarantor: AccountRef,
+        data: &[T],
+    ) -> Result<(GuaranteeSigned, Vec<MerkleProof>)>
+    where
+        T: Serialize<SignatureSerializer>,
+    {
+        let hashes = data
+            .iter()
+            .map(|data| {
+                ::rkyv::to_bytes::<_, 64>(data)
+                    .map(|bytes| Hash::with_bytes(&bytes))
+                    .map_err(Into::into)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let tree = MerkleTree::new(&hashes)?;
+
+        let metadata = self.build_unsigned_raw(guarantor, tree.root());
+        let proofs = (0..tree.len())
+            .map(|index| tree.proof(index).expect("index in range"))
+            .collect();
+        Ok((Signer::sign(account, metadata)?, proofs))
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::{Read, Write};
+
+use anyhow::{bail, Result};
+use bytecheck::CheckBytes;
+use rkyv::{
+    de::deserializers::SharedDeserializeMap, validation::validators::DefaultValidator, Archive,
+    Deserialize, Serialize,
+};
+
+use crate::{
+    account::{GuaranteeSigned, GuarantorSigned},
+    archived::deserialize_unaligned,
+    data::Data,
+    signature::SignatureSerializer,
+    signed::IsSigned,
+    value::hash::Hash,
+};
+
+/// The zstd level, where `0` stands for the library default.
+const ZSTD_LEVEL: i32 = 0;
+
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash))]
+pub enum Compression {
+    /// The LZ4 frame format, favoring the speed
+    Lz4,
+    /// The zstd frame format, favoring the ratio
+    Zstd,
+}
+
+/// The rkyv-archived data, compressed as is.
+///
+/// The payload is decompressed only when accessed, and `Metadata.hash` is defined over
+/// the uncompressed bytes, so that the compression does not change the identity.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct Compressed {
+    pub compression: Compression,
+    /// The length of the uncompressed bytes
+    pub len: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl Compressed {
+    pub fn compress<T>(data: &T, compression: Compression) -> Result<Self>
+    where
+        T: Serialize<SignatureSerializer>,
+    {
+        let bytes = ::rkyv::to_bytes::<_, 64>(data)?;
+        Self::compress_archived(&bytes, compression)
+    }
+
+    /// Compresses the archived bytes of `T` as is.
+    pub fn compress_archived(bytes: &[u8], compression: Compression) -> Result<Self> {
+        let compressed = match compression {
+            Compression::Lz4 => {
+                let mut encoder = ::lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(bytes)?;
+                encoder.finish()?
+            }
+            Compression::Zstd => ::zstd::stream::encode_all(bytes, ZSTD_LEVEL)?,
+        };
+
+        Ok(Self {
+            compression,
+            len: bytes.len().try_into()?,
+            bytes: compressed,
+        })
+    }
+
+    /// Decompresses the archived bytes of `T`.
+    pub fn decompress_archived(&self) -> Result<Vec<u8>> {
+        let len = usize::try_from(self.len)?;
+
+        // note: the buffer grows with the actual output, not the recorded length
+        let mut bytes = Vec::new();
+        match self.compression {
+            Compression::Lz4 => ::lz4_flex::frame::FrameDecoder::new(&self.bytes[..])
+                .take(self.len.saturating_add(1))
+                .read_to_end(&mut bytes)?,
+            Compression::Zstd => ::zstd::stream::Decoder::new(&self.bytes[..])?
+                .take(self.len.saturating_add(1))
+                .read_to_end(&mut bytes)?,
+        };
+
+        if bytes.len() != len {
+            bail!(
+                "decompressed length mismatching: expected {len}, but given {}",
+                bytes.len(),
+            );
+        }
+        Ok(bytes)
+    }
+
+    pub fn decompress<T>(&self) -> Result<T>
+    where
+        T: Archive,
+        <T as Archive>::Archived:
+            for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+    {
+        let bytes = self.decompress_archived()?;
+        deserialize_unaligned(&bytes)
+    }
+
+    /// Returns the hash of the uncompressed bytes, as `Metadata.hash` commits to.
+    pub fn to_hash(&self) -> Result<Hash> {
+        self.decompress_archived()
+            .map(|bytes| Hash::with_bytes(&bytes))
+    }
+}
+
+macro_rules! impl_decompress_for {
+    ( $( $metadata:ty ,)* ) => { $(
+        impl Data<$metadata, Compressed> {
+            /// Decompresses the payload, checking it against `Metadata.hash`.
+            ///
+            /// The signatures are not verified here; see [`Verifier`](crate::account::Verifier).
+            pub fn decompress<T>(&self) -> Result<Data<$metadata, T>>
+            where
+                T: IsSigned + Archive,
+                <T as Archive>::Archived:
+                    for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+            {
+                let bytes = self.data.decompress_archived()?;
+                if Hash::with_bytes(&bytes) != self.metadata.hash {
+                    bail!("hash mismatching");
+                }
+
+                Ok(Data {
+                    metadata: self.metadata,
+                    data: deserialize_unaligned(&bytes)?,
+                })
+            }
+        }
+    )* };
+}
+
+impl_decompress_for!(GuaranteeSigned, GuarantorSigned,);
@@ -2,14 +2,18 @@ use std::marker::PhantomData;
 
 use anyhow::Result;
 use bytecheck::CheckBytes;
+use rand::RngCore;
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::{
     account::{Account, AccountRef, GuaranteeSigned, GuarantorSigned, Signer, Verifier},
-    metadata::{Metadata, MetadataBuilder},
+    compressed::{Compressed, Compression},
+    ipld::ToIpld,
+    metadata::{Clock, Metadata, MetadataBuilder},
+    sealed::{Commitment, Sealed},
     signature::SignatureSerializer,
     signed::IsSigned,
-    value::chrono::DateTime,
+    value::{chrono::DateTime, hash::Hash, nonce::Nonce},
 };
 
 #[derive(
@@ -65,6 +69,11 @@ where
         // skip validation of raw data
         self.metadata.verify(guarantor)
     }
+
+    fn verify_dag_cbor(&self, guarantor: Option<&AccountRef>) -> Result<()> {
+        // skip validation of raw data
+        self.metadata.verify_dag_cbor(guarantor)
+    }
 }
 
 impl<RawData> Data<GuaranteeSigned, RawData>
@@ -84,6 +93,13 @@ where
             data: self.data,
         })
     }
+
+    pub fn sign_dag_cbor(self, guarantor: &Account) -> Result<Data<GuarantorSigned, RawData>> {
+        Ok(Data {
+            metadata: GuarantorSigned::sign_dag_cbor(guarantor, self.metadata)?,
+            data: self.data,
+        })
+    }
 }
 
 impl<'a, Metadata, RawData> Data<Metadata, &'a RawData>
@@ -105,11 +121,36 @@ pub struct DataBuilder<T> {
 }
 
 impl<T> DataBuilder<T> {
+    pub fn nonce(mut self, nonce: Nonce) -> Self {
+        self.metadata = self.metadata.nonce(nonce);
+        self
+    }
+
+    pub fn created_date(mut self, date: DateTime) -> Self {
+        self.metadata = self.metadata.created_date(date);
+        self
+    }
+
     pub fn expiration_date(mut self, date: DateTime) -> Self {
         self.metadata = self.metadata.expiration_date(date);
         self
     }
 
+    pub fn schema(mut self, schema: Hash) -> Self {
+        self.metadata = self.metadata.schema(schema);
+        self
+    }
+
+    pub fn clock(mut self, clock: impl Clock + Send + Sync + 'static) -> Self {
+        self.metadata = self.metadata.clock(clock);
+        self
+    }
+
+    pub fn rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.metadata = self.metadata.rng(rng);
+        self
+    }
+
     pub fn build<'a>(
         self,
         account: &Account,
@@ -140,4 +181,85 @@ impl<T> DataBuilder<T> {
             data,
         })
     }
+
+    pub fn build_dag_cbor<'a>(
+        self,
+        account: &Account,
+        guarantor: AccountRef,
+        data: &'a T,
+    ) -> Result<Data<GuaranteeSigned, &'a T>>
+    where
+        T: IsSigned + ToIpld,
+    {
+        let metadata = self.metadata.build_dag_cbor(account, guarantor, data)?;
+
+        Ok(Data { metadata, data })
+    }
+
+    pub fn build_owned_dag_cbor(
+        self,
+        account: &Account,
+        guarantor: AccountRef,
+        data: T,
+    ) -> Result<Data<GuaranteeSigned, T>>
+    where
+        T: IsSigned + ToIpld,
+    {
+        Ok(Data {
+            metadata: self.build_dag_cbor(account, guarantor, &data)?.metadata,
+            data,
+        })
+    }
+
+    /// Compresses the data, committing `Metadata.hash` to the uncompressed bytes
+    /// as [`Self::build`] does.
+    pub fn build_compressed(
+        self,
+        account: &Account,
+        guarantor: AccountRef,
+        data: &T,
+        compression: Compression,
+    ) -> Result<Data<GuaranteeSigned, Compressed>>
+    where
+        T: IsSigned + Serialize<SignatureSerializer>,
+    {
+        let bytes = ::rkyv::to_bytes::<_, 64>(data)?;
+        let compressed = Compressed::compress_archived(&bytes, compression)?;
+
+        let metadata = self
+            .metadata
+            .build_unsigned_raw(guarantor, Hash::with_bytes(&bytes));
+
+        Ok(Data {
+            metadata: Signer::sign(account, metadata)?,
+            data: compressed,
+        })
+    }
+
+    /// Seals the data to the recipients, committing `Metadata.hash` to the chosen bytes.
+    pub fn build_sealed(
+        self,
+        account: &Account,
+        guarantor: AccountRef,
+        data: &T,
+        recipients: &[AccountRef],
+        commitment: Commitment,
+    ) -> Result<Data<GuaranteeSigned, Sealed>>
+    where
+        T: IsSigned + Serialize<SignatureSerializer>,
+    {
+        let bytes = ::rkyv::to_bytes::<_, 64>(data)?;
+        let sealed = Sealed::seal_archived(&bytes, recipients, commitment)?;
+
+        let hash = match commitment {
+            Commitment::Plaintext => Hash::with_bytes(&bytes),
+            Commitment::Ciphertext => sealed.to_hash(),
+        };
+        let metadata = self.metadata.build_unsigned_raw(guarantor, hash);
+
+        Ok(Data {
+            metadata: Signer::sign(account, metadata)?,
+            data: sealed,
+        })
+    }
 }
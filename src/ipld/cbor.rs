@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use super::Ipld;
+use crate::value::hash::Hash;
+
+/// CBOR major types
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_STRING: u8 = 3;
+const MAJOR_LIST: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// CBOR simple values
+const SIMPLE_FALSE: u8 = 0xf4;
+const SIMPLE_TRUE: u8 = 0xf5;
+const SIMPLE_NULL: u8 = 0xf6;
+const SIMPLE_F64: u8 = 0xfb;
+
+/// The CBOR tag of the CID links
+const TAG_CID: u64 = 42;
+
+/// The multibase prefix of the binary CIDs, required by the tag 42
+const CID_PREFIX: u8 = 0x00;
+
+/// should be enough for any sane document, and protects the stack from the malicious ones
+const MAX_DEPTH: usize = 256;
+
+impl Ipld {
+    /// Encodes into the canonical DAG-CBOR.
+    ///
+    /// Integers and lengths take the shortest form, floats are always 64-bit, and the map
+    /// keys are sorted by their length first and then bytewise.
+    pub fn to_dag_cbor(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        encode(self, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Decodes from the DAG-CBOR, rejecting any non-canonical form so that the signatures
+    /// over the bytes stay reproducible.
+    pub fn from_dag_cbor(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = Decoder { bytes, pos: 0 };
+        let ipld = decoder.decode(0)?;
+        if decoder.pos != bytes.len() {
+            bail!(
+                "trailing bytes after the DAG-CBOR: {}",
+                bytes.len() - decoder.pos
+            );
+        }
+        Ok(ipld)
+    }
+}
+
+fn write_header(buf: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        buf.push(major | value as u8);
+    } else if value <= u8::MAX as u64 {
+        buf.push(major | 24);
+        buf.push(value as u8);
+    } else if value <= u16::MAX as u64 {
+        buf.push(major | 25);
+        buf.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= u32::MAX as u64 {
+        buf.push(major | 26);
+        buf.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        buf.push(major | 27);
+        buf.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn write_len(buf: &mut Vec<u8>, major: u8, len: usize) {
+    write_header(buf, major, len as u64)
+}
+
+fn encode(ipld: &Ipld, buf: &mut Vec<u8>) -> Result<()> {
+    match ipld {
+        Ipld::Null => buf.push(SIMPLE_NULL),
+        Ipld::Bool(false) => buf.push(SIMPLE_FALSE),
+        Ipld::Bool(true) => buf.push(SIMPLE_TRUE),
+        Ipld::Integer(value) => {
+            let (major, value) = if *value >= 0 {
+                (MAJOR_UNSIGNED, u64::try_from(*value))
+            } else {
+                (MAJOR_NEGATIVE, u64::try_from(-1 - *value))
+            };
+            let value = value.map_err(|_| anyhow!("integer out of the CBOR range"))?;
+            write_header(buf, major, value)
+        }
+        Ipld::Float(value) => {
+            if !value.is_finite() {
+                bail!("DAG-CBOR does not support the non-finite floats: {value}");
+            }
+            buf.push(SIMPLE_F64);
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+        Ipld::String(value) => {
+            write_len(buf, MAJOR_STRING, value.len());
+            buf.extend_from_slice(value.as_bytes());
+        }
+        Ipld::Bytes(value) => {
+            write_len(buf, MAJOR_BYTES, value.len());
+            buf.extend_from_slice(value);
+        }
+        Ipld::List(values) => {
+            write_len(buf, MAJOR_LIST, values.len());
+            for value in values {
+                encode(value, buf)?;
+            }
+        }
+        Ipld::Map(values) => {
+            let mut values: Vec<_> = values.iter().collect();
+            values.sort_by(|(a, _), (b, _)| compare_keys(a, b));
+
+            write_len(buf, MAJOR_MAP, values.len());
+            for (key, value) in values {
+                write_len(buf, MAJOR_STRING, key.len());
+                buf.extend_from_slice(key.as_bytes());
+                encode(value, buf)?;
+            }
+        }
+        Ipld::Link(hash) => {
+            let bytes: Vec<u8> = (*hash).into();
+
+            write_header(buf, MAJOR_TAG, TAG_CID);
+            write_len(buf, MAJOR_BYTES, 1 + bytes.len());
+            buf.push(CID_PREFIX);
+            buf.extend_from_slice(&bytes);
+        }
+    }
+    Ok(())
+}
+
+/// The canonical order of the map keys: the shorter first, and then bytewise.
+fn compare_keys(a: &str, b: &str) -> ::core::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn read(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| anyhow!("unexpected end of the DAG-CBOR"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.read(N)?.try_into().expect("the length is checked"))
+    }
+
+    fn read_header(&mut self) -> Result<(u8, u64)> {
+        let [byte] = self.read_array()?;
+        let major = byte >> 5;
+        let (value, min) = match byte & 0x1f {
+            info @ 0..=23 => return Ok((major, info.into())),
+            24 => (u8::from_be_bytes(self.read_array()?).into(), 24),
+            25 => (u16::from_be_bytes(self.read_array()?).into(), 1 << 8),
+            26 => (u32::from_be_bytes(self.read_array()?).into(), 1 << 16),
+            27 => (u64::from_be_bytes(self.read_array()?), 1 << 32),
+            _ => bail!("unsupported CBOR header: {byte:#04x}"),
+        };
+        if value < min {
+            bail!("non-canonical CBOR integer: {value}");
+        }
+        Ok((major, value))
+    }
+
+    fn read_len(&mut self, value: u64) -> Result<usize> {
+        // note: every item takes at least one byte, so longer lengths are always malformed
+        usize::try_from(value)
+            .ok()
+            .filter(|&len| len <= self.bytes.len() - self.pos)
+            .ok_or_else(|| anyhow!("unexpected end of the DAG-CBOR"))
+    }
+
+    fn read_string(&mut self, value: u64) -> Result<&'a str> {
+        let len = self.read_len(value)?;
+        ::core::str::from_utf8(self.read(len)?).map_err(Into::into)
+    }
+
+    fn decode(&mut self, depth: usize) -> Result<Ipld> {
+        if depth > MAX_DEPTH {
+            bail!("too deep DAG-CBOR: more than {MAX_DEPTH} levels");
+        }
+
+        match self.bytes.get(self.pos).copied() {
+            Some(SIMPLE_FALSE) => {
+                self.pos += 1;
+                return Ok(Ipld::Bool(false));
+            }
+            Some(SIMPLE_TRUE) => {
+                self.pos += 1;
+                return Ok(Ipld::Bool(true));
+            }
+            Some(SIMPLE_NULL) => {
+                self.pos += 1;
+                return Ok(Ipld::Null);
+            }
+            Some(SIMPLE_F64) => {
+                self.pos += 1;
+                let value = f64::from_be_bytes(self.read_array()?);
+                if !value.is_finite() {
+                    bail!("DAG-CBOR does not support the non-finite floats: {value}");
+                }
+                return Ok(Ipld::Float(value));
+            }
+            Some(byte) if byte >> 5 == 7 => bail!("unsupported CBOR simple value: {byte:#04x}"),
+            _ => {}
+        }
+
+        let (major, value) = self.read_header()?;
+        match major {
+            MAJOR_UNSIGNED => Ok(Ipld::Integer(value.into())),
+            MAJOR_NEGATIVE => Ok(Ipld::Integer(-1 - i128::from(value))),
+            MAJOR_BYTES => {
+                let len = self.read_len(value)?;
+                self.read(len).map(|bytes| Ipld::Bytes(bytes.to_vec()))
+            }
+            MAJOR_STRING => self
+                .read_string(value)
+                .map(|value| Ipld::String(value.to_string())),
+            MAJOR_LIST => {
+                let len = self.read_len(value)?;
+                (0..len)
+                    .map(|_| self.decode(depth + 1))
+                    .collect::<Result<_>>()
+                    .map(Ipld::List)
+            }
+            MAJOR_MAP => {
+                let len = self.read_len(value)?;
+                let mut values = BTreeMap::new();
+                let mut last_key: Option<&str> = None;
+                for _ in 0..len {
+                    let key = match self.read_header()? {
+                        (MAJOR_STRING, value) => self.read_string(value)?,
+                        (major, _) => bail!("expected a string key, but given major {major}"),
+                    };
+                    if let Some(last_key) = last_key {
+                        if compare_keys(last_key, key).is_ge() {
+                            bail!("unsorted or duplicated DAG-CBOR key: {key:?}");
+                        }
+                    }
+                    last_key = Some(key);
+
+                    values.insert(key.to_string(), self.decode(depth + 1)?);
+                }
+                Ok(Ipld::Map(values))
+            }
+            MAJOR_TAG if value == TAG_CID => match self.read_header()? {
+                (MAJOR_BYTES, value) => {
+                    let len = self.read_len(value)?;
+                    match self.read(len)? {
+                        [CID_PREFIX, bytes @ ..] => {
+                            Hash::try_from(bytes).map(Ipld::Link).map_err(Into::into)
+                        }
+                        _ => bail!("missing the multibase prefix of the CID"),
+                    }
+                }
+                (major, _) => bail!("expected the CID bytes, but given major {major}"),
+            },
+            MAJOR_TAG => bail!("unsupported CBOR tag: {value}"),
+            _ => unreachable!("the major type has 3 bits"),
+        }
+    }
+}
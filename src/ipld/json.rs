@@ -0,0 +1,103 @@
+use anyhow::{anyhow, bail, Result};
+use cid::multibase::Base;
+use serde_json::{Map, Number, Value as Json};
+
+use super::Ipld;
+
+/// The reserved key of the links and bytes
+const KEY_RESERVED: &str = "/";
+
+/// The key of the base64-encoded bytes
+const KEY_BYTES: &str = "bytes";
+
+impl Ipld {
+    /// Encodes into the canonical DAG-JSON, without any whitespace.
+    ///
+    /// The map keys are sorted bytewise, the links become `{"/": cid}` and the bytes
+    /// become `{"/": {"bytes": base64}}`.
+    pub fn to_dag_json(&self) -> Result<String> {
+        to_json(self).and_then(|json| ::serde_json::to_string(&json).map_err(Into::into))
+    }
+
+    /// Decodes from the DAG-JSON.
+    pub fn from_dag_json(s: &str) -> Result<Self> {
+        ::serde_json::from_str(s)
+            .map_err(Into::into)
+            .and_then(|json| from_json(&json))
+    }
+}
+
+fn reserved(value: Json) -> Json {
+    Json::Object(Map::from_iter([(KEY_RESERVED.to_string(), value)]))
+}
+
+fn to_json(ipld: &Ipld) -> Result<Json> {
+    Ok(match ipld {
+        Ipld::Null => Json::Null,
+        Ipld::Bool(value) => Json::Bool(*value),
+        Ipld::Integer(value) => {
+            if let Ok(value) = i64::try_from(*value) {
+                value.into()
+            } else if let Ok(value) = u64::try_from(*value) {
+                value.into()
+            } else {
+                bail!("integer out of the DAG-JSON range: {value}")
+            }
+        }
+        Ipld::Float(value) => Number::from_f64(*value)
+            .map(Json::Number)
+            .ok_or_else(|| anyhow!("DAG-JSON does not support the non-finite floats: {value}"))?,
+        Ipld::String(value) => Json::String(value.clone()),
+        Ipld::Bytes(value) => reserved(Json::Object(Map::from_iter([(
+            KEY_BYTES.to_string(),
+            Json::String(Base::Base64.encode(value)),
+        )]))),
+        Ipld::List(values) => Json::Array(values.iter().map(to_json).collect::<Result<_>>()?),
+        Ipld::Map(values) => {
+            // note: such maps could not be told apart from the links and bytes
+            if values.len() == 1 && values.contains_key(KEY_RESERVED) {
+                bail!("DAG-JSON does not support the maps of the single {KEY_RESERVED:?} key");
+            }
+            Json::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), to_json(value)?)))
+                    .collect::<Result<_>>()?,
+            )
+        }
+        Ipld::Link(hash) => reserved(Json::String(hash.to_string())),
+    })
+}
+
+fn from_json(json: &Json) -> Result<Ipld> {
+    Ok(match json {
+        Json::Null => Ipld::Null,
+        Json::Bool(value) => Ipld::Bool(*value),
+        Json::Number(value) => match (value.as_i64(), value.as_u64(), value.as_f64()) {
+            (Some(value), _, _) => Ipld::Integer(value.into()),
+            (None, Some(value), _) => Ipld::Integer(value.into()),
+            (None, None, Some(value)) => Ipld::Float(value),
+            (None, None, None) => bail!("malformed number: {value}"),
+        },
+        Json::String(value) => Ipld::String(value.clone()),
+        Json::Array(values) => Ipld::List(values.iter().map(from_json).collect::<Result<_>>()?),
+        Json::Object(values) => match values.get(KEY_RESERVED) {
+            Some(Json::String(cid)) if values.len() == 1 => Ipld::Link(cid.parse()?),
+            Some(Json::Object(inner)) if values.len() == 1 => match inner.get(KEY_BYTES) {
+                Some(Json::String(bytes)) if inner.len() == 1 => Ipld::Bytes(
+                    Base::Base64
+                        .decode(bytes)
+                        .map_err(|_| anyhow!("malformed base64 bytes: {bytes:?}"))?,
+                ),
+                _ => bail!("malformed DAG-JSON bytes: {json}"),
+            },
+            Some(_) if values.len() == 1 => bail!("malformed DAG-JSON link: {json}"),
+            _ => Ipld::Map(
+                values
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), from_json(value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+        },
+    })
+}
@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    account::{AccountRef, GuaranteeSigned, GuarantorSigned, Identity, Verifier},
+    data::Data,
+    metadata::Metadata,
+    signature::{PublicKey, Signature},
+    signed::IsSigned,
+    value::{chrono::DateTime, hash::Hash, nonce::Nonce, uuid::Uuid},
+};
+
+mod cbor;
+mod json;
+mod value;
+
+/// A node of the IPLD data model.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ipld {
+    Null,
+    Bool(bool),
+    /// Integers within the CBOR range, i.e. `-2^64..2^64`
+    Integer(i128),
+    /// Finite floats
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    List(Vec<Ipld>),
+    Map(BTreeMap<String, Ipld>),
+    /// CID links, encoded as the CBOR tag 42
+    Link(Hash),
+}
+
+impl Ipld {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "bool",
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "float",
+            Self::String(_) => "string",
+            Self::Bytes(_) => "bytes",
+            Self::List(_) => "list",
+            Self::Map(_) => "map",
+            Self::Link(_) => "link",
+        }
+    }
+
+    /// Returns the fields of the map, rejecting the unknown ones.
+    fn as_fields(&self, name: &str, keys: &[&str]) -> Result<&BTreeMap<String, Self>> {
+        match self {
+            Self::Map(fields) => match fields.keys().find(|key| !keys.contains(&key.as_str())) {
+                Some(key) => bail!("unexpected field of {name}: {key:?}"),
+                None => Ok(fields),
+            },
+            _ => bail!("expected a map of {name}, but given {}", self.kind()),
+        }
+    }
+}
+
+fn field<T: FromIpld>(fields: &BTreeMap<String, Ipld>, key: &str) -> Result<T> {
+    fields
+        .get(key)
+        .ok_or_else(|| anyhow!("missing field: {key:?}"))
+        .and_then(T::from_ipld)
+        .map_err(|e| anyhow!("malformed field {key:?}: {e}"))
+}
+
+/// Types with a fixed IPLD representation, encodable as DAG-CBOR and DAG-JSON.
+pub trait ToIpld {
+    fn to_ipld(&self) -> Ipld;
+
+    fn to_dag_cbor(&self) -> Result<Vec<u8>> {
+        self.to_ipld().to_dag_cbor()
+    }
+
+    fn to_dag_json(&self) -> Result<String> {
+        self.to_ipld().to_dag_json()
+    }
+}
+
+pub trait FromIpld: Sized {
+    fn from_ipld(ipld: &Ipld) -> Result<Self>;
+
+    fn from_dag_cbor(bytes: &[u8]) -> Result<Self> {
+        Ipld::from_dag_cbor(bytes).and_then(|ipld| Self::from_ipld(&ipld))
+    }
+
+    fn from_dag_json(s: &str) -> Result<Self> {
+        Ipld::from_dag_json(s).and_then(|ipld| Self::from_ipld(&ipld))
+    }
+}
+
+impl ToIpld for Ipld {
+    fn to_ipld(&self) -> Ipld {
+        self.clone()
+    }
+}
+
+impl FromIpld for Ipld {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        Ok(ipld.clone())
+    }
+}
+
+impl<T: ToIpld + ?Sized> ToIpld for &T {
+    fn to_ipld(&self) -> Ipld {
+        (**self).to_ipld()
+    }
+}
+
+impl<T: ToIpld + ?Sized> ToIpld for Box<T> {
+    fn to_ipld(&self) -> Ipld {
+        (**self).to_ipld()
+    }
+}
+
+impl<T: FromIpld> FromIpld for Box<T> {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        T::from_ipld(ipld).map(Box::new)
+    }
+}
+
+impl ToIpld for bool {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Bool(*self)
+    }
+}
+
+impl FromIpld for bool {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::Bool(value) => Ok(*value),
+            _ => bail!("expected a bool, but given {}", ipld.kind()),
+        }
+    }
+}
+
+macro_rules! impl_for_integers {
+    ( $( $ty:ty ,)* ) => { $(
+        impl ToIpld for $ty {
+            fn to_ipld(&self) -> Ipld {
+                Ipld::Integer((*self).into())
+            }
+        }
+
+        impl FromIpld for $ty {
+            fn from_ipld(ipld: &Ipld) -> Result<Self> {
+                match ipld {
+                    Ipld::Integer(value) => (*value).try_into().map_err(|_| {
+                        anyhow!("integer overflow: {value} for {}", stringify!($ty))
+                    }),
+                    _ => bail!("expected an integer, but given {}", ipld.kind()),
+                }
+            }
+        }
+    )* };
+}
+
+impl_for_integers!(i8, i16, i32, i64, u8, u16, u32, u64,);
+
+impl ToIpld for f64 {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Float(*self)
+    }
+}
+
+impl FromIpld for f64 {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::Float(value) => Ok(*value),
+            _ => bail!("expected a float, but given {}", ipld.kind()),
+        }
+    }
+}
+
+impl ToIpld for str {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::String(self.to_string())
+    }
+}
+
+impl ToIpld for String {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::String(self.clone())
+    }
+}
+
+impl FromIpld for String {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::String(value) => Ok(value.clone()),
+            _ => bail!("expected a string, but given {}", ipld.kind()),
+        }
+    }
+}
+
+impl<T: ToIpld> ToIpld for [T] {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::List(self.iter().map(ToIpld::to_ipld).collect())
+    }
+}
+
+impl<T: ToIpld> ToIpld for Vec<T> {
+    fn to_ipld(&self) -> Ipld {
+        self.as_slice().to_ipld()
+    }
+}
+
+impl<T: FromIpld> FromIpld for Vec<T> {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::List(values) => values.iter().map(T::from_ipld).collect(),
+            _ => bail!("expected a list, but given {}", ipld.kind()),
+        }
+    }
+}
+
+impl<T: ToIpld> ToIpld for BTreeMap<String, T> {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Map(
+            self.iter()
+                .map(|(key, value)| (key.clone(), value.to_ipld()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: FromIpld> FromIpld for BTreeMap<String, T> {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::Map(values) => values
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), T::from_ipld(value)?)))
+                .collect(),
+            _ => bail!("expected a map, but given {}", ipld.kind()),
+        }
+    }
+}
+
+impl<T: ToIpld> ToIpld for Option<T> {
+    fn to_ipld(&self) -> Ipld {
+        match self {
+            Some(value) => value.to_ipld(),
+            None => Ipld::Null,
+        }
+    }
+}
+
+impl<T: FromIpld> FromIpld for Option<T> {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::Null => Ok(None),
+            ipld => T::from_ipld(ipld).map(Some),
+        }
+    }
+}
+
+impl ToIpld for Hash {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Link(*self)
+    }
+}
+
+impl FromIpld for Hash {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        match ipld {
+            Ipld::Link(value) => Ok(*value),
+            _ => bail!("expected a link, but given {}", ipld.kind()),
+        }
+    }
+}
+
+fn bytes_from_ipld<const N: usize>(ipld: &Ipld) -> Result<[u8; N]> {
+    match ipld {
+        Ipld::Bytes(value) => value
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("expected {N} bytes, but given {}", value.len())),
+        _ => bail!("expected bytes, but given {}", ipld.kind()),
+    }
+}
+
+impl ToIpld for Uuid {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Bytes(self.as_bytes().to_vec())
+    }
+}
+
+impl FromIpld for Uuid {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        bytes_from_ipld(ipld).map(|bytes| Self(::uuid::Uuid::from_bytes(bytes)))
+    }
+}
+
+impl ToIpld for Nonce {
+    fn to_ipld(&self) -> Ipld {
+        self.0.to_ipld()
+    }
+}
+
+impl FromIpld for Nonce {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        Uuid::from_ipld(ipld).map(Self)
+    }
+}
+
+impl ToIpld for DateTime {
+    /// Encodes as the RFC 3339 string in UTC, e.g. `2022-01-01T00:00:00.123Z`.
+    fn to_ipld(&self) -> Ipld {
+        Ipld::String(self.to_rfc3339_opts(::chrono::SecondsFormat::AutoSi, true))
+    }
+}
+
+impl FromIpld for DateTime {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        let value = String::from_ipld(ipld)?;
+        let date = ::chrono::DateTime::parse_from_rfc3339(&value)
+            .map(|date| Self(date.with_timezone(&::chrono::Utc)))?;
+
+        // the other forms of the same date would break the signatures
+        if date.to_ipld() != *ipld {
+            bail!("non-canonical date: {value:?}");
+        }
+        Ok(date)
+    }
+}
+
+impl ToIpld for PublicKey {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Bytes(self.0.to_bytes().to_vec())
+    }
+}
+
+impl FromIpld for PublicKey {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        let bytes: [u8; 32] = bytes_from_ipld(ipld)?;
+        ::ed25519_dalek::PublicKey::from_bytes(&bytes)
+            .map(Self)
+            .map_err(Into::into)
+    }
+}
+
+impl ToIpld for Signature {
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Bytes(self.0.to_bytes().to_vec())
+    }
+}
+
+impl FromIpld for Signature {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        let bytes: [u8; 64] = bytes_from_ipld(ipld)?;
+        ::ed25519_dalek::Signature::from_bytes(&bytes)
+            .map(Self)
+            .map_err(Into::into)
+    }
+}
+
+impl ToIpld for AccountRef {
+    fn to_ipld(&self) -> Ipld {
+        self.public_key.to_ipld()
+    }
+}
+
+impl FromIpld for AccountRef {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        PublicKey::from_ipld(ipld).map(|public_key| Self { public_key })
+    }
+}
+
+/// Implements the IPLD representation of the structs as the maps of their fields.
+macro_rules! impl_for_structs {
+    ( $( $ty:ident { $( $field:ident ,)* } )* ) => { $(
+        impl ToIpld for $ty {
+            fn to_ipld(&self) -> Ipld {
+                Ipld::Map(BTreeMap::from([
+                    $( (stringify!($field).to_string(), self.$field.to_ipld()), )*
+                ]))
+            }
+        }
+
+        impl FromIpld for $ty {
+            fn from_ipld(ipld: &Ipld) -> Result<Self> {
+                let fields = ipld.as_fields(stringify!($ty), &[ $( stringify!($field), )* ])?;
+                Ok(Self {
+                    $( $field: field(fields, stringify!($field))?, )*
+                })
+            }
+        }
+    )* };
+}
+
+impl_for_structs!(
+    Identity {
+        account,
+        signature,
+    }
+    Metadata {
+        nonce,
+        created_date,
+        expiration_date,
+        guarantor,
+        hash,
+        schema,
+    }
+    GuaranteeSigned {
+        guarantee,
+        data,
+    }
+    GuarantorSigned {
+        guarantor,
+        data,
+    }
+);
+
+impl<Metadata, RawData> ToIpld for Data<Metadata, RawData>
+where
+    Metadata: Verifier + ToIpld,
+    RawData: IsSigned + ToIpld,
+{
+    fn to_ipld(&self) -> Ipld {
+        Ipld::Map(BTreeMap::from([
+            ("metadata".to_string(), self.metadata.to_ipld()),
+            ("data".to_string(), self.data.to_ipld()),
+        ]))
+    }
+}
+
+impl<Metadata, RawData> FromIpld for Data<Metadata, RawData>
+where
+    Metadata: Verifier + FromIpld,
+    RawData: IsSigned + FromIpld,
+{
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        let fields = ipld.as_fields("Data", &["metadata", "data"])?;
+        Ok(Self {
+            metadata: field(fields, "metadata")?,
+            data: field(fields, "data")?,
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use ndarray::{ArcArray, IxDyn};
+
+use super::{FromIpld, Ipld, ToIpld};
+use crate::value::{
+    array::Array, bytes::Bytes, chrono::DateTime, text::Text, uuid::Uuid, Value, ValueType,
+};
+
+impl ToIpld for ValueType {
+    fn to_ipld(&self) -> Ipld {
+        match ::serde_json::to_value(self).expect("ValueType should be serialized") {
+            ::serde_json::Value::String(ty) => Ipld::String(ty),
+            ty => unreachable!("ValueType should be a string: {ty}"),
+        }
+    }
+}
+
+impl FromIpld for ValueType {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        String::from_ipld(ipld)
+            .and_then(|ty| ::serde_json::from_value(ty.into()).map_err(Into::into))
+    }
+}
+
+/// Follows the layout of [`Value::to_json_annotated`], so that the exact type is signed too.
+///
+/// The bytes, hashes and UUIDs take their native IPLD kinds instead of the strings.
+impl ToIpld for Value {
+    fn to_ipld(&self) -> Ipld {
+        let value = match self {
+            Self::None | Self::Dyn => None,
+            Self::Bool(value) => Some(value.to_ipld()),
+            Self::I8(value) => Some(value.to_ipld()),
+            Self::I16(value) => Some(value.to_ipld()),
+            Self::I32(value) => Some(value.to_ipld()),
+            Self::I64(value) => Some(value.to_ipld()),
+            Self::U8(value) => Some(value.to_ipld()),
+            Self::U16(value) => Some(value.to_ipld()),
+            Self::U32(value) => Some(value.to_ipld()),
+            Self::U64(value) => Some(value.to_ipld()),
+            Self::F32(value) => Some(float_to_ipld((*value).into())),
+            Self::F64(value) => Some(float_to_ipld(*value)),
+            Self::Bytes(value) => Some(Ipld::Bytes(value.0.clone())),
+            Self::Text(value) => Some(Ipld::Map(BTreeMap::from([
+                ("msg".to_string(), value.msg.to_ipld()),
+                ("lang".to_string(), Ipld::String(value.lang.to_string())),
+            ]))),
+            Self::Hash(value) => Some(value.to_ipld()),
+            Self::DateTime(value) => Some(value.to_ipld()),
+            Self::Uuid(value) => Some(value.to_ipld()),
+            Self::UnitInterval(value) => Some(Ipld::Float(value.to_num())),
+            Self::List(values) => Some(values.to_ipld()),
+            Self::Map(values) => Some(values.to_ipld()),
+            Self::ArrayU8(value) => Some(Ipld::Bytes(value.iter().copied().collect())),
+            Self::ArrayI64(value) => Some(Ipld::List(value.iter().map(i64::to_ipld).collect())),
+            Self::ArrayF32(value) => Some(Ipld::List(
+                value
+                    .iter()
+                    .map(|&value| float_to_ipld(value.into()))
+                    .collect(),
+            )),
+            Self::ArrayF64(value) => Some(Ipld::List(
+                value.iter().map(|&value| float_to_ipld(value)).collect(),
+            )),
+        };
+
+        let mut ipld = BTreeMap::new();
+        ipld.insert(Self::JSON_KEY_TYPE.to_string(), self.value_type().to_ipld());
+        if let Some(shape) = self.shape() {
+            ipld.insert(
+                Self::JSON_KEY_SHAPE.to_string(),
+                Ipld::List(
+                    shape
+                        .iter()
+                        .map(|&len| Ipld::Integer(len as i128))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(value) = value {
+            ipld.insert(Self::JSON_KEY_VALUE.to_string(), value);
+        }
+        Ipld::Map(ipld)
+    }
+}
+
+impl FromIpld for Value {
+    fn from_ipld(ipld: &Ipld) -> Result<Self> {
+        let fields = ipld.as_fields(
+            "Value",
+            &[
+                Self::JSON_KEY_TYPE,
+                Self::JSON_KEY_VALUE,
+                Self::JSON_KEY_SHAPE,
+            ],
+        )?;
+        let ty: ValueType = super::field(fields, Self::JSON_KEY_TYPE)?;
+        let value = || {
+            fields
+                .get(Self::JSON_KEY_VALUE)
+                .ok_or_else(|| anyhow!("missing the value of {ty:?}"))
+        };
+        let shape = || -> Result<Vec<usize>> {
+            let shape: Vec<u64> = super::field(fields, Self::JSON_KEY_SHAPE)?;
+            shape
+                .into_iter()
+                .map(|len| len.try_into().map_err(Into::into))
+                .collect()
+        };
+
+        let value = match ty {
+            ValueType::None => Self::None,
+            ValueType::Dyn => Self::Dyn,
+            ValueType::Bool => Self::Bool(FromIpld::from_ipld(value()?)?),
+            ValueType::I8 => Self::I8(FromIpld::from_ipld(value()?)?),
+            ValueType::I16 => Self::I16(FromIpld::from_ipld(value()?)?),
+            ValueType::I32 => Self::I32(FromIpld::from_ipld(value()?)?),
+            ValueType::I64 => Self::I64(FromIpld::from_ipld(value()?)?),
+            ValueType::U8 => Self::U8(FromIpld::from_ipld(value()?)?),
+            ValueType::U16 => Self::U16(FromIpld::from_ipld(value()?)?),
+            ValueType::U32 => Self::U32(FromIpld::from_ipld(value()?)?),
+            ValueType::U64 => Self::U64(FromIpld::from_ipld(value()?)?),
+            ValueType::F32 => Self::F32(float_from_ipld(value()?)? as f32),
+            ValueType::F64 => Self::F64(float_from_ipld(value()?)?),
+            ValueType::Bytes => match value()? {
+                Ipld::Bytes(value) => Self::Bytes(Bytes(value.clone())),
+                value => bail!("expected bytes, but given {}", value.kind()),
+            },
+            ValueType::Text => {
+                let fields = value()?.as_fields("Text", &["msg", "lang"])?;
+                Self::Text(Text {
+                    msg: super::field(fields, "msg")?,
+                    lang: super::field::<String>(fields, "lang")?.parse()?,
+                })
+            }
+            ValueType::Hash => Self::Hash(FromIpld::from_ipld(value()?)?),
+            ValueType::DateTime => Self::DateTime(DateTime::from_ipld(value()?)?),
+            ValueType::Uuid => Self::Uuid(Uuid::from_ipld(value()?)?),
+            ValueType::UnitInterval => Self::F64(f64::from_ipld(value()?)?).cast(ty)?,
+            ValueType::List => Self::List(FromIpld::from_ipld(value()?)?),
+            ValueType::Map => Self::Map(FromIpld::from_ipld(value()?)?),
+            ValueType::ArrayU8 => match value()? {
+                Ipld::Bytes(value) => Self::ArrayU8(to_array(shape()?, value.clone())?),
+                value => bail!("expected bytes, but given {}", value.kind()),
+            },
+            ValueType::ArrayI64 => Self::ArrayI64(to_array(shape()?, Vec::from_ipld(value()?)?)?),
+            ValueType::ArrayF32 => Self::ArrayF32(to_array(
+                shape()?,
+                list_from_ipld(value()?, |value| float_from_ipld(value).map(|v| v as f32))?,
+            )?),
+            ValueType::ArrayF64 => Self::ArrayF64(to_array(
+                shape()?,
+                list_from_ipld(value()?, float_from_ipld)?,
+            )?),
+        };
+
+        // the unused fields would be dropped silently, breaking the signatures
+        if value.to_ipld() != *ipld {
+            bail!("non-canonical value of {ty:?}");
+        }
+        Ok(value)
+    }
+}
+
+fn to_array<A>(shape: Vec<usize>, data: Vec<A>) -> Result<Array<A, IxDyn>> {
+    ArcArray::from_shape_vec(IxDyn(&shape), data)
+        .map(Array)
+        .map_err(Into::into)
+}
+
+fn list_from_ipld<A>(ipld: &Ipld, f: impl Fn(&Ipld) -> Result<A>) -> Result<Vec<A>> {
+    match ipld {
+        Ipld::List(values) => values.iter().map(f).collect(),
+        _ => bail!("expected a list, but given {}", ipld.kind()),
+    }
+}
+
+/// DAG-CBOR forbids the non-finite floats, so they are written as the strings like JSON.
+fn float_to_ipld(value: f64) -> Ipld {
+    if value.is_finite() {
+        Ipld::Float(value)
+    } else if value.is_nan() {
+        Ipld::String("NaN".to_string())
+    } else if value.is_sign_positive() {
+        Ipld::String("Infinity".to_string())
+    } else {
+        Ipld::String("-Infinity".to_string())
+    }
+}
+
+fn float_from_ipld(ipld: &Ipld) -> Result<f64> {
+    match ipld {
+        Ipld::Float(value) => Ok(*value),
+        Ipld::String(value) => match value.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => bail!("malformed float: {value:?}"),
+        },
+        _ => bail!("expected a float, but given {}", ipld.kind()),
+    }
+}
@@ -8,10 +8,19 @@ pub extern crate ndarray;
 pub extern crate ordered_float;
 pub extern crate uuid;
 
+mod archived;
+
 pub mod account;
+pub mod batch;
+pub mod compressed;
 pub mod credit;
 pub mod data;
+pub mod ipld;
+pub mod log;
 pub mod metadata;
+pub mod sealed;
 pub mod signature;
 pub mod signed;
+pub mod store;
+pub mod timestamp;
 pub mod value;
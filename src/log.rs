@@ -0,0 +1,360 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::OnceLock,
+};
+
+use anyhow::{bail, Result};
+use bytecheck::CheckBytes;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::{
+    account::{Account, AccountRef, GuaranteeSigned, GuarantorSigned, Identity, Signer, Verifier},
+    data::Data,
+    signature::SignatureSerializer,
+    signed::IsSigned,
+    value::hash::Hash,
+};
+
+/// The signed payload of a log entry, committing to its parent entry.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(bound(archive = "
+    T: Archive,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+"))]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes))]
+pub struct Chained<T> {
+    /// The hash of the previous entry, or `None` for the first one
+    pub parent: Option<Hash>,
+    pub data: T,
+}
+
+// note: the derived traits would bound `T` instead of its archived type
+impl<T> ::core::fmt::Debug for ArchivedChained<T>
+where
+    T: Archive,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+{
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("ArchivedChained")
+            .field("parent", &self.parent)
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+impl<T> PartialEq for ArchivedChained<T>
+where
+    T: Archive,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.parent == other.parent && self.data == other.data
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(bound(archive = "
+    T: Archive,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+"))]
+#[archive_attr(derive(CheckBytes))]
+pub struct LogEntry<T>
+where
+    T: IsSigned,
+{
+    pub data: Data<GuaranteeSigned, Chained<T>>,
+    /// The signature of the guarantor, as [`GuarantorSigned`] would carry
+    pub countersignature: Option<Identity>,
+}
+
+impl<T> LogEntry<T>
+where
+    T: IsSigned,
+{
+    /// Returns the hash of the signed metadata, which the countersignature does not change.
+    pub fn to_hash(&self) -> Result<Hash> {
+        ::rkyv::to_bytes::<_, 64>(&self.data.metadata)
+            .map(|bytes| Hash::with_bytes(&bytes))
+            .map_err(Into::into)
+    }
+
+    pub fn parent(&self) -> Option<Hash> {
+        self.data.data.parent
+    }
+
+    pub fn to_guarantor_signed(&self) -> Option<GuarantorSigned> {
+        self.countersignature.map(|guarantor| GuarantorSigned {
+            guarantor,
+            data: self.data.metadata,
+        })
+    }
+
+    pub fn countersign(&mut self, guarantor: &Account) -> Result<()> {
+        let signed: GuarantorSigned = Signer::sign(guarantor, self.data.metadata)?;
+        self.countersignature = Some(signed.guarantor);
+        Ok(())
+    }
+}
+
+impl<T> LogEntry<T>
+where
+    T: IsSigned + Archive + Serialize<SignatureSerializer>,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+{
+    /// Verifies the signatures and the payload against `Metadata.hash`.
+    pub fn verify(&self) -> Result<()> {
+        match self.to_guarantor_signed() {
+            Some(signed) => signed.verify(None)?,
+            None => self.data.metadata.verify(None)?,
+        }
+
+        let bytes = ::rkyv::to_bytes::<_, 64>(&self.data.data)?;
+        if Hash::with_bytes(&bytes) != self.data.metadata.hash {
+            bail!("hash mismatching");
+        }
+        Ok(())
+    }
+}
+
+/// The entries sharing a parent.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fork {
+    pub parent: Option<Hash>,
+    /// The hashes of the diverged entries, sorted
+    pub children: Vec<Hash>,
+}
+
+/// Detects the forks among the entries, such as the ones gathered from the replicas.
+pub fn find_forks<'a, T>(entries: impl IntoIterator<Item = &'a LogEntry<T>>) -> Result<Vec<Fork>>
+where
+    T: 'a + IsSigned,
+{
+    let mut children: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for entry in entries {
+        children
+            .entry(entry.parent())
+            .or_default()
+            .insert(entry.to_hash()?);
+    }
+
+    Ok(children
+        .into_iter()
+        .filter(|(_, children)| children.len() > 1)
+        .map(|(parent, children)| Fork {
+            parent,
+            children: children.into_iter().collect(),
+        })
+        .collect())
+}
+
+/// A tamper-evident, append-only log, where every entry commits to the previous one.
+#[derive(
+    Clone, Debug, Archive, Serialize, Deserialize, ::serde::Serialize, ::serde::Deserialize,
+)]
+#[archive(bound(archive = "
+    T: Archive,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+"))]
+#[archive_attr(derive(CheckBytes))]
+pub struct SignedLog<T>
+where
+    T: IsSigned,
+{
+    entries: Vec<LogEntry<T>>,
+    /// The positions of the entries by their hashes, built on the first lookup
+    #[with(::rkyv::with::Skip)]
+    #[serde(skip)]
+    positions: OnceLock<BTreeMap<Hash, usize>>,
+}
+
+impl<T> Default for SignedLog<T>
+where
+    T: IsSigned,
+{
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            positions: OnceLock::new(),
+        }
+    }
+}
+
+// note: the cached positions are derived from the entries
+impl<T> PartialEq for SignedLog<T>
+where
+    T: IsSigned + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<T> Eq for SignedLog<T> where T: IsSigned + Eq {}
+
+impl<T> ::core::hash::Hash for SignedLog<T>
+where
+    T: IsSigned + ::core::hash::Hash,
+{
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        self.entries.hash(state)
+    }
+}
+
+impl<T> SignedLog<T>
+where
+    T: IsSigned,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[LogEntry<T>] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn head(&self) -> Option<&LogEntry<T>> {
+        self.entries.last()
+    }
+
+    pub fn head_hash(&self) -> Result<Option<Hash>> {
+        self.head().map(LogEntry::to_hash).transpose()
+    }
+
+    fn positions(&self) -> Result<&BTreeMap<Hash, usize>> {
+        if let Some(positions) = self.positions.get() {
+            return Ok(positions);
+        }
+
+        let positions = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| entry.to_hash().map(|hash| (hash, index)))
+            .collect::<Result<_>>()?;
+        Ok(self.positions.get_or_init(|| positions))
+    }
+
+    pub fn get(&self, hash: &Hash) -> Result<Option<&LogEntry<T>>> {
+        Ok(self
+            .positions()?
+            .get(hash)
+            .map(|&index| &self.entries[index]))
+    }
+
+    /// Adds the countersignature of the guarantor to the entry.
+    pub fn countersign(&mut self, guarantor: &Account, hash: &Hash) -> Result<()> {
+        match self.positions()?.get(hash).copied() {
+            Some(index) => self.entries[index].countersign(guarantor),
+            None => bail!("no such entry: {}", hash.to_string()),
+        }
+    }
+
+    /// Detects where the logs diverged, if any.
+    ///
+    /// Both logs are linear chains, so they diverge at most once and the fork is unique;
+    /// see [`find_forks`] for the entries gathered from more replicas.
+    pub fn find_fork(&self, other: &Self) -> Result<Option<Fork>> {
+        find_forks(self.entries.iter().chain(&other.entries)).map(|forks| forks.into_iter().next())
+    }
+}
+
+impl<T> SignedLog<T>
+where
+    T: IsSigned + Archive + Serialize<SignatureSerializer>,
+    <T as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+{
+    /// Verifies and restores the log from the entries, such as the ones loaded from a store.
+    pub fn from_entries(entries: Vec<LogEntry<T>>) -> Result<Self> {
+        let log = Self {
+            entries,
+            positions: OnceLock::new(),
+        };
+        log.verify()?;
+        Ok(log)
+    }
+
+    /// Signs the data as guarantee, chained to the head, returning the hash of the new entry.
+    pub fn append(&mut self, account: &Account, guarantor: AccountRef, data: T) -> Result<Hash> {
+        let data = Data::builder().build_owned(
+            account,
+            guarantor,
+            Chained {
+                parent: self.head_hash()?,
+                data,
+            },
+        )?;
+
+        self.append_entry(LogEntry {
+            data,
+            countersignature: None,
+        })
+    }
+
+    /// Verifies and appends the entry signed elsewhere, returning its hash.
+    pub fn append_entry(&mut self, entry: LogEntry<T>) -> Result<Hash> {
+        entry.verify()?;
+
+        let head = self.head_hash()?;
+        if entry.parent() != head {
+            match entry.parent() {
+                Some(parent) if self.get(&parent)?.is_some() => {
+                    bail!("fork detected at {}", parent.to_string())
+                }
+                _ => bail!("parent mismatching"),
+            }
+        }
+
+        let hash = entry.to_hash()?;
+        if let Some(positions) = self.positions.get_mut() {
+            positions.insert(hash, self.entries.len());
+        }
+        self.entries.push(entry);
+        Ok(hash)
+    }
+
+    /// Verifies every entry and its link to the previous one.
+    pub fn verify(&self) -> Result<()> {
+        let mut parent = None;
+        for entry in &self.entries {
+            entry.verify()?;
+            if entry.parent() != parent {
+                bail!("broken chain");
+            }
+            parent = Some(entry.to_hash()?);
+        }
+        Ok(())
+    }
+}
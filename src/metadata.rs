@@ -1,9 +1,11 @@
 use anyhow::Result;
 use bytecheck::CheckBytes;
+use rand::RngCore;
 use rkyv::{Archive, Deserialize, Serialize};
 
 use crate::{
     account::{Account, AccountRef, GuaranteeSigned, Signer},
+    ipld::ToIpld,
     signature::SignatureSerializer,
     signed::IsSigned,
     value::{chrono::DateTime, hash::Hash, nonce::Nonce},
@@ -32,33 +34,106 @@ pub struct Metadata {
     pub expiration_date: Option<DateTime>,
     pub guarantor: AccountRef,
     pub hash: Hash,
+    /// The hash of the [`Schema`](crate::value::schema::Schema) the payload conforms to
+    pub schema: Option<Hash>,
 }
 
 impl Metadata {
     pub fn builder() -> MetadataBuilder {
         MetadataBuilder {
+            nonce: None,
+            created_date: None,
             expiration_date: None,
+            schema: None,
+            clock: None,
+            rng: None,
         }
     }
 }
 
+/// The source of the created dates.
+pub trait Clock {
+    fn now(&self) -> DateTime;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> DateTime,
+{
+    fn now(&self) -> DateTime {
+        self()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        DateTime::now()
+    }
+}
+
 pub struct MetadataBuilder {
+    nonce: Option<Nonce>,
+    created_date: Option<DateTime>,
     expiration_date: Option<DateTime>,
+    schema: Option<Hash>,
+    clock: Option<Box<dyn Clock + Send + Sync>>,
+    rng: Option<Box<dyn RngCore + Send>>,
 }
 
 impl MetadataBuilder {
+    /// Uses the nonce instead of generating one, such as for the golden files.
+    pub fn nonce(mut self, nonce: Nonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Uses the created date instead of the clock.
+    pub fn created_date(mut self, date: DateTime) -> Self {
+        self.created_date = Some(date);
+        self
+    }
+
+    /// Reads the created date from the clock instead of [`SystemClock`].
+    pub fn clock(mut self, clock: impl Clock + Send + Sync + 'static) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Generates the nonce from the source instead of the OS, such as a seeded one.
+    pub fn rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
     pub fn expiration_date(mut self, date: DateTime) -> Self {
         self.expiration_date = Some(date);
         self
     }
 
+    pub fn schema(mut self, schema: Hash) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
     pub fn build_unsigned_raw(self, guarantor: AccountRef, hash: Hash) -> Metadata {
         Metadata {
-            nonce: Nonce::generate(),
-            created_date: DateTime::now(),
+            nonce: match (self.nonce, self.rng) {
+                (Some(nonce), _) => nonce,
+                (None, Some(mut rng)) => Nonce::with_rng(&mut rng),
+                (None, None) => Nonce::generate(),
+            },
+            created_date: match (self.created_date, self.clock) {
+                (Some(date), _) => date,
+                (None, Some(clock)) => clock.now(),
+                (None, None) => SystemClock.now(),
+            },
             expiration_date: self.expiration_date,
             guarantor,
             hash,
+            schema: self.schema,
         }
     }
 
@@ -86,4 +161,28 @@ impl MetadataBuilder {
         self.build_unsigned(guarantor, data)
             .and_then(|metadata| Signer::sign(account, metadata))
     }
+
+    /// Hashes the DAG-CBOR bytes of the data, as IPLD tools would address it.
+    pub fn build_unsigned_dag_cbor<T>(self, guarantor: AccountRef, data: &T) -> Result<Metadata>
+    where
+        T: ToIpld,
+    {
+        data.to_dag_cbor()
+            .map(|bytes| Hash::with_dag_cbor(&bytes))
+            .map(|hash| self.build_unsigned_raw(guarantor, hash))
+    }
+
+    /// Builds and signs the metadata over the DAG-CBOR bytes instead of the archived ones.
+    pub fn build_dag_cbor<T>(
+        self,
+        account: &Account,
+        guarantor: AccountRef,
+        data: &T,
+    ) -> Result<GuaranteeSigned>
+    where
+        T: ToIpld,
+    {
+        self.build_unsigned_dag_cbor(guarantor, data)
+            .and_then(|metadata| GuaranteeSigned::sign_dag_cbor(account, metadata))
+    }
 }
@@ -0,0 +1,395 @@
+use anyhow::{anyhow, bail, Result};
+use bytecheck::CheckBytes;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305,
+};
+use curve25519_dalek::{
+    constants::X25519_BASEPOINT, edwards::CompressedEdwardsY, montgomery::MontgomeryPoint,
+    scalar::Scalar,
+};
+use hkdf::Hkdf;
+use rand::RngCore;
+use rkyv::{
+    de::deserializers::SharedDeserializeMap, validation::validators::DefaultValidator, Archive,
+    Deserialize, Serialize,
+};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{
+    account::{Account, AccountRef, GuaranteeSigned, GuarantorSigned},
+    archived::deserialize_unaligned,
+    data::Data,
+    signature::SignatureSerializer,
+    signed::IsSigned,
+    value::hash::Hash,
+};
+
+/// Encryption scheme and version, bound to the derived keys
+const INFO: &[u8] = b"ipi-sealed-v1";
+
+/// Binds the content key to the envelope, as ChaCha20-Poly1305 does not commit to the key
+const KEY_COMMITMENT_INFO: &[u8] = b"ipi-sealed-v1-key-commitment";
+
+/// Which bytes `Metadata.hash` of the sealed data commits to.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash))]
+pub enum Commitment {
+    /// The archived plaintext, so that the identity does not depend on the encryption.
+    ///
+    /// Note that anyone can then test a guessed plaintext against the hash.
+    Plaintext,
+    /// The encrypted body and the key commitment, revealing nothing about the plaintext.
+    ///
+    /// The recipients are not committed, so that they can be added later.
+    Ciphertext,
+}
+
+/// The content key of the payload, wrapped for a recipient.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct WrappedKey {
+    pub recipient: AccountRef,
+    /// The X25519 public key of the ephemeral sender key
+    pub ephemeral: [u8; 32],
+    pub key: [u8; 32],
+    pub tag: [u8; 16],
+}
+
+/// The rkyv-archived data, encrypted with ChaCha20-Poly1305 under a random content key.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct Sealed {
+    pub commitment: Commitment,
+    /// The HKDF-SHA-256 output of the content key, so that it opens with a single key only
+    pub key_commitment: [u8; 32],
+    /// The wrapped content keys, sorted by the recipient
+    pub recipients: Vec<WrappedKey>,
+    pub nonce: [u8; 12],
+    pub ciphertext: Vec<u8>,
+}
+
+impl Sealed {
+    pub fn seal<T>(data: &T, recipients: &[AccountRef], commitment: Commitment) -> Result<Self>
+    where
+        T: Serialize<SignatureSerializer>,
+    {
+        let bytes = ::rkyv::to_bytes::<_, 64>(data)?;
+        Self::seal_archived(&bytes, recipients, commitment)
+    }
+
+    /// Seals the archived bytes of `T` as is.
+    pub fn seal_archived(
+        bytes: &[u8],
+        recipients: &[AccountRef],
+        commitment: Commitment,
+    ) -> Result<Self> {
+        if recipients.is_empty() {
+            bail!("no recipients to seal for");
+        }
+
+        let mut recipients = recipients.to_vec();
+        recipients.sort();
+        recipients.dedup();
+
+        let mut key = [0; 32];
+        let mut nonce = [0; 12];
+        ::rand::rngs::OsRng.fill_bytes(&mut key);
+        ::rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+        let key_commitment = commit_key(&key);
+        let ciphertext = ChaCha20Poly1305::new(&key.into())
+            .encrypt(
+                &nonce.into(),
+                Payload {
+                    msg: bytes,
+                    aad: &to_aad(commitment, &key_commitment),
+                },
+            )
+            .map_err(|_| anyhow!("failed to encrypt the payload"))?;
+
+        Ok(Self {
+            commitment,
+            key_commitment,
+            recipients: recipients
+                .iter()
+                .map(|recipient| wrap_key(&key, recipient))
+                .collect::<Result<_>>()?,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Returns the hash of the key commitment and the encrypted body,
+    /// as [`Commitment::Ciphertext`] commits to.
+    pub fn to_hash(&self) -> Hash {
+        Hash::with_bytes(&[&self.key_commitment[..], &self.ciphertext].concat())
+    }
+
+    pub fn recipient(&self, account: &AccountRef) -> Option<&WrappedKey> {
+        self.recipients
+            .binary_search_by(|wrapped| wrapped.recipient.cmp(account))
+            .ok()
+            .map(|index| &self.recipients[index])
+    }
+
+    pub fn is_recipient(&self, account: &AccountRef) -> bool {
+        self.recipient(account).is_some()
+    }
+
+    /// Wraps the content key for the new recipients, without re-encrypting the body.
+    ///
+    /// The `account` should be one of the recipients. Note that the recipients cannot be
+    /// revoked by removing them, as they may have kept the content key.
+    pub fn add_recipients(&mut self, account: &Account, recipients: &[AccountRef]) -> Result<()> {
+        let key = self.unwrap_key(account)?;
+
+        // wrap all keys first, leaving the recipients as is on failure
+        let mut recipients = recipients
+            .iter()
+            .filter(|recipient| !self.is_recipient(recipient))
+            .map(|recipient| wrap_key(&key, recipient))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.recipients.append(&mut recipients);
+        self.recipients.sort_by_key(|wrapped| wrapped.recipient);
+        self.recipients.dedup_by_key(|wrapped| wrapped.recipient);
+        Ok(())
+    }
+
+    fn unwrap_key(&self, account: &Account) -> Result<[u8; 32]> {
+        self.recipient(&account.account_ref())
+            .ok_or_else(|| anyhow!("not a recipient of the sealed data"))
+            .and_then(|wrapped| unwrap_key(wrapped, account))
+    }
+
+    /// Decrypts the archived bytes of `T`.
+    pub fn open_archived(&self, account: &Account) -> Result<Vec<u8>> {
+        let key = self.unwrap_key(account)?;
+        if commit_key(&key) != self.key_commitment {
+            bail!("key commitment mismatching");
+        }
+
+        ChaCha20Poly1305::new(&key.into())
+            .decrypt(
+                &self.nonce.into(),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &to_aad(self.commitment, &self.key_commitment),
+                },
+            )
+            .map_err(|_| anyhow!("failed to decrypt the payload"))
+    }
+
+    pub fn open<T>(&self, account: &Account) -> Result<T>
+    where
+        T: Archive,
+        <T as Archive>::Archived:
+            for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+    {
+        let bytes = self.open_archived(account)?;
+        deserialize_unaligned(&bytes)
+    }
+}
+
+fn commit_key(key: &[u8; 32]) -> [u8; 32] {
+    let mut commitment = [0; 32];
+    Hkdf::<Sha256>::new(None, key)
+        .expand(KEY_COMMITMENT_INFO, &mut commitment)
+        .expect("32 bytes are a valid length for HKDF-SHA-256");
+    commitment
+}
+
+fn to_aad(commitment: Commitment, key_commitment: &[u8; 32]) -> Vec<u8> {
+    [&[commitment as u8][..], key_commitment].concat()
+}
+
+/// Converts the ed25519 public key into the X25519 one.
+fn to_x25519_public(account: &AccountRef) -> Result<MontgomeryPoint> {
+    CompressedEdwardsY(account.public_key.to_bytes())
+        .decompress()
+        .map(|point| point.to_montgomery())
+        .ok_or_else(|| anyhow!("malformed public key"))
+}
+
+/// Converts the ed25519 secret key into the X25519 one, as ed25519 expands it.
+fn to_x25519_secret(account: &Account) -> Scalar {
+    let hash = Sha512::digest(account.keypair.secret.as_bytes());
+    clamp(hash[..32].try_into().expect("SHA-512 has 64 bytes"))
+}
+
+fn clamp(mut bytes: [u8; 32]) -> Scalar {
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(bytes)
+}
+
+fn derive_key(
+    secret: &Scalar,
+    public: &MontgomeryPoint,
+    ephemeral: &MontgomeryPoint,
+    recipient: &MontgomeryPoint,
+) -> Result<ChaCha20Poly1305> {
+    let shared = public * secret;
+    if shared.as_bytes() == &[0; 32] {
+        bail!("low-order X25519 public key");
+    }
+
+    let info = [INFO, ephemeral.as_bytes(), recipient.as_bytes()].concat();
+    let mut key = [0; 32];
+    Hkdf::<Sha256>::new(None, shared.as_bytes())
+        .expand(&info, &mut key)
+        .expect("32 bytes are a valid length for HKDF-SHA-256");
+    Ok(ChaCha20Poly1305::new(&key.into()))
+}
+
+fn wrap_key(key: &[u8; 32], recipient: &AccountRef) -> Result<WrappedKey> {
+    let mut ephemeral_secret = [0; 32];
+    ::rand::rngs::OsRng.fill_bytes(&mut ephemeral_secret);
+    let ephemeral_secret = clamp(ephemeral_secret);
+    let ephemeral = X25519_BASEPOINT * ephemeral_secret;
+
+    let public = to_x25519_public(recipient)?;
+    let wrapped = derive_key(&ephemeral_secret, &public, &ephemeral, &public)?
+        // note: the nonce is never reused, as every derived key is used only once
+        .encrypt(
+            &Default::default(),
+            Payload {
+                msg: key,
+                aad: recipient.public_key.as_bytes(),
+            },
+        )
+        .map_err(|_| anyhow!("failed to wrap the content key"))?;
+    let (key, tag) = wrapped.split_at(32);
+
+    Ok(WrappedKey {
+        recipient: *recipient,
+        ephemeral: ephemeral.to_bytes(),
+        key: key
+            .try_into()
+            .expect("ChaCha20-Poly1305 preserves the length"),
+        tag: tag.try_into().expect("Poly1305 tag has 16 bytes"),
+    })
+}
+
+fn unwrap_key(wrapped: &WrappedKey, account: &Account) -> Result<[u8; 32]> {
+    let secret = to_x25519_secret(account);
+    let ephemeral = MontgomeryPoint(wrapped.ephemeral);
+    let public = X25519_BASEPOINT * secret;
+
+    let key = derive_key(&secret, &ephemeral, &ephemeral, &public)?
+        .decrypt(
+            &Default::default(),
+            Payload {
+                msg: &[&wrapped.key[..], &wrapped.tag[..]].concat(),
+                aad: wrapped.recipient.public_key.as_bytes(),
+            },
+        )
+        .map_err(|_| anyhow!("failed to unwrap the content key"))?;
+    Ok(key
+        .try_into()
+        .expect("ChaCha20-Poly1305 preserves the length"))
+}
+
+impl ArchivedSealed {
+    /// Looks up the recipient without deserializing the envelope.
+    pub fn recipient(&self, account: &AccountRef) -> Option<&ArchivedWrappedKey> {
+        self.recipients
+            .binary_search_by(|wrapped| {
+                wrapped
+                    .recipient
+                    .partial_cmp(account)
+                    .expect("public keys are totally ordered")
+            })
+            .ok()
+            .map(|index| &self.recipients[index])
+    }
+}
+
+impl Account {
+    /// Decrypts the data sealed to this account.
+    pub fn open<T>(&self, sealed: &Sealed) -> Result<T>
+    where
+        T: Archive,
+        <T as Archive>::Archived:
+            for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+    {
+        sealed.open(self)
+    }
+}
+
+macro_rules! impl_open_for {
+    ( $( $metadata:ty ,)* ) => { $(
+        impl Data<$metadata, Sealed> {
+            /// Decrypts the payload, checking it against `Metadata.hash`.
+            ///
+            /// The signatures are not verified here; see [`Verifier`](crate::account::Verifier).
+            pub fn open<T>(&self, account: &Account) -> Result<Data<$metadata, T>>
+            where
+                T: IsSigned + Archive,
+                <T as Archive>::Archived:
+                    for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+            {
+                let bytes = self.data.open_archived(account)?;
+
+                let hash = match self.data.commitment {
+                    Commitment::Plaintext => Hash::with_bytes(&bytes),
+                    Commitment::Ciphertext => self.data.to_hash(),
+                };
+                if hash != self.metadata.hash {
+                    bail!("hash mismatching");
+                }
+
+                Ok(Data {
+                    metadata: self.metadata,
+                    data: deserialize_unaligned(&bytes)?,
+                })
+            }
+        }
+    )* };
+}
+
+impl_open_for!(GuaranteeSigned, GuarantorSigned,);
@@ -17,6 +17,22 @@ pub trait IsSigned {
     {
         ::rkyv::to_bytes(self)
     }
+
+    /// Returns the hash which `Metadata.hash` commits to,
+    /// as [`MetadataBuilder::build_unsigned`] does.
+    ///
+    /// Returns `None` if the hash cannot be computed without the secrets,
+    /// e.g. the plaintext commitment of [`Sealed`](crate::sealed::Sealed).
+    ///
+    /// [`MetadataBuilder::build_unsigned`]: crate::metadata::MetadataBuilder::build_unsigned
+    fn to_payload_hash(&self) -> ::anyhow::Result<Option<crate::value::hash::Hash>>
+    where
+        Self: ::rkyv::Serialize<crate::signature::SignatureSerializer> + Sized,
+    {
+        ::rkyv::to_bytes(self)
+            .map(|bytes| Some(crate::value::hash::Hash::with_bytes(&bytes)))
+            .map_err(Into::into)
+    }
 }
 
 impl IsSigned for () {}
@@ -176,7 +192,31 @@ where
         true
     }
 }
+impl IsSigned for crate::compressed::Compressed {
+    fn to_payload_hash(&self) -> ::anyhow::Result<Option<crate::value::hash::Hash>> {
+        self.to_hash().map(Some)
+    }
+}
+impl<T> IsSigned for crate::log::Chained<T> where T: IsSigned {}
+impl<T> IsSigned for crate::log::LogEntry<T> where T: IsSigned {}
+impl<T> IsSigned for crate::log::SignedLog<T> where T: IsSigned {}
 impl IsSigned for crate::metadata::Metadata {}
+impl IsSigned for crate::sealed::Sealed {
+    fn to_payload_hash(&self) -> ::anyhow::Result<Option<crate::value::hash::Hash>> {
+        match self.commitment {
+            // note: the plaintext is checked on opening, with the key
+            crate::sealed::Commitment::Plaintext => Ok(None),
+            crate::sealed::Commitment::Ciphertext => Ok(Some(self.to_hash())),
+        }
+    }
+}
+
+impl IsSigned for crate::timestamp::Timestamp {}
+impl IsSigned for crate::timestamp::TimestampSigned {
+    fn is_signed() -> bool {
+        true
+    }
+}
 
 impl IsSigned for crate::value::Value {}
 impl IsSigned for crate::value::ValueType {}
@@ -0,0 +1,117 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use memmap2::Mmap;
+use tempfile::NamedTempFile;
+
+use super::{Object, ObjectBytes, Store};
+use crate::value::hash::Hash;
+
+const DIR_OBJECTS: &str = "objects";
+const DIR_TMP: &str = "tmp";
+
+/// A filesystem store, where the objects are memory-mapped on reads.
+///
+/// The objects are laid out as `objects/<shard>/<hash>`, where the shard is the
+/// next-to-last two characters of the hash, and written atomically through `tmp`.
+#[derive(Clone, Debug)]
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        fs::create_dir_all(root.join(DIR_OBJECTS))?;
+        fs::create_dir_all(root.join(DIR_TMP))?;
+
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, hash: &Hash) -> PathBuf {
+        let name = hash.to_string();
+        let shard = &name[name.len() - 3..name.len() - 1];
+        self.root.join(DIR_OBJECTS).join(shard).join(name)
+    }
+}
+
+impl Store for FsStore {
+    fn put_unchecked(&self, hash: &Hash, bytes: &[u8]) -> Result<()> {
+        let path = self.path(hash);
+        fs::create_dir_all(path.parent().expect("objects are sharded"))?;
+
+        let mut file = NamedTempFile::new_in(self.root.join(DIR_TMP))?;
+        file.write_all(bytes)?;
+        file.as_file().sync_all()?;
+        file.persist(path)?;
+        Ok(())
+    }
+
+    fn get(&self, hash: &Hash) -> Result<Option<Object>> {
+        let file = match File::open(self.path(hash)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // SAFETY: the objects are never modified in place, but only replaced by renaming
+        let bytes = unsafe { Mmap::map(&file)? };
+        Ok(Some(Object(ObjectBytes::Mapped(bytes))))
+    }
+
+    fn contains(&self, hash: &Hash) -> Result<bool> {
+        Ok(self.path(hash).try_exists()?)
+    }
+
+    fn remove(&self, hash: &Hash) -> Result<bool> {
+        match fs::remove_file(self.path(hash)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<Hash>> {
+        let mut hashes = Vec::new();
+        for shard in fs::read_dir(self.root.join(DIR_OBJECTS))? {
+            let shard = shard?;
+            if !shard.file_type()?.is_dir() {
+                continue;
+            }
+
+            for object in fs::read_dir(shard.path())? {
+                // skip the unknown files
+                if let Some(hash) = object?
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.parse().ok())
+                {
+                    hashes.push(hash);
+                }
+            }
+        }
+        hashes.sort();
+        Ok(hashes)
+    }
+
+    /// Also removes the temporary files left by the interrupted writes.
+    ///
+    /// Note that it should not run concurrently with the writes.
+    fn gc(&self, live: &BTreeSet<Hash>) -> Result<Vec<Hash>> {
+        let removed = super::collect_garbage(self, live)?;
+
+        for file in fs::read_dir(self.root.join(DIR_TMP))? {
+            fs::remove_file(file?.path())?;
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,454 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::RwLock,
+};
+
+use anyhow::{anyhow, bail, Result};
+use bytecheck::CheckBytes;
+use rkyv::{
+    de::deserializers::SharedDeserializeMap, validation::validators::DefaultValidator, Archive,
+    Deserialize, Serialize,
+};
+
+use super::Store;
+use crate::{
+    account::{AccountRef, GuaranteeSigned, GuarantorSigned, Verifier},
+    data::{ArchivedData, Data},
+    ipld::ToIpld,
+    metadata::Metadata,
+    signature::SignatureSerializer,
+    signed::{IsSigned, Serializer},
+    value::{chrono::DateTime, hash::Hash},
+};
+
+/// The indexed fields of a stored envelope.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// `Metadata.guarantor`
+    pub guarantor: AccountRef,
+    /// `Identity.account` of the guarantee
+    pub guarantee: AccountRef,
+    /// `Identity.account` of the guarantor, once countersigned
+    pub signed_guarantor: Option<AccountRef>,
+    pub created_date: DateTime,
+    pub expiration_date: Option<DateTime>,
+}
+
+impl IndexEntry {
+    pub fn is_expired(&self, now: &DateTime) -> bool {
+        matches!(&self.expiration_date, Some(date) if date < now)
+    }
+}
+
+pub trait Indexed {
+    fn to_index_entry(&self) -> IndexEntry;
+}
+
+impl Indexed for GuaranteeSigned {
+    fn to_index_entry(&self) -> IndexEntry {
+        IndexEntry {
+            guarantor: self.data.guarantor,
+            guarantee: self.guarantee.account,
+            signed_guarantor: None,
+            created_date: self.data.created_date,
+            expiration_date: self.data.expiration_date,
+        }
+    }
+}
+
+impl Indexed for GuarantorSigned {
+    fn to_index_entry(&self) -> IndexEntry {
+        IndexEntry {
+            signed_guarantor: Some(self.guarantor.account),
+            ..self.data.to_index_entry()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    guarantor: Option<AccountRef>,
+    guarantee: Option<AccountRef>,
+    signed_guarantor: Option<AccountRef>,
+    created_date: Option<(DateTime, DateTime)>,
+    expiring_before: Option<DateTime>,
+}
+
+fn unmatched_account(query: Option<AccountRef>, account: Option<AccountRef>) -> bool {
+    matches!(query, Some(query) if Some(query) != account)
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn guarantor(mut self, account: AccountRef) -> Self {
+        self.guarantor = Some(account);
+        self
+    }
+
+    pub fn guarantee(mut self, account: AccountRef) -> Self {
+        self.guarantee = Some(account);
+        self
+    }
+
+    pub fn signed_guarantor(mut self, account: AccountRef) -> Self {
+        self.signed_guarantor = Some(account);
+        self
+    }
+
+    /// Matches the envelopes created in `start..end`.
+    pub fn created_between(mut self, start: DateTime, end: DateTime) -> Self {
+        self.created_date = Some((start, end));
+        self
+    }
+
+    /// Matches the envelopes expiring before the date, but not the unexpiring ones.
+    pub fn expiring_before(mut self, date: DateTime) -> Self {
+        self.expiring_before = Some(date);
+        self
+    }
+
+    pub fn matches(&self, entry: &IndexEntry) -> bool {
+        if unmatched_account(self.guarantor, Some(entry.guarantor))
+            || unmatched_account(self.guarantee, Some(entry.guarantee))
+            || unmatched_account(self.signed_guarantor, entry.signed_guarantor)
+        {
+            return false;
+        }
+
+        let (start, end) = self.created_range();
+        if entry.created_date < start || entry.created_date >= end {
+            return false;
+        }
+
+        match (self.expiring_before, entry.expiration_date) {
+            (Some(date), Some(expiration)) => expiration < date,
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    fn created_range(&self) -> (DateTime, DateTime) {
+        self.created_date
+            .unwrap_or((DateTime::MIN_DATETIME, DateTime::MAX_DATETIME))
+    }
+}
+
+type DateIndex = BTreeMap<DateTime, BTreeSet<Hash>>;
+
+fn insert_date(index: &mut DateIndex, date: DateTime, hash: Hash) {
+    index.entry(date).or_default().insert(hash);
+}
+
+fn remove_date(index: &mut DateIndex, date: DateTime, hash: &Hash) {
+    if let Some(hashes) = index.get_mut(&date) {
+        hashes.remove(hash);
+        if hashes.is_empty() {
+            index.remove(&date);
+        }
+    }
+}
+
+fn range_dates(index: &DateIndex, start: DateTime, end: DateTime) -> impl Iterator<Item = &Hash> {
+    index
+        .range(start..end)
+        .flat_map(|(_, hashes)| hashes.iter())
+}
+
+/// The in-memory secondary indexes over the stored envelopes.
+#[derive(Clone, Debug, Default)]
+pub struct Index {
+    entries: BTreeMap<Hash, IndexEntry>,
+    by_guarantor: BTreeMap<AccountRef, DateIndex>,
+    by_guarantee: BTreeMap<AccountRef, DateIndex>,
+    by_signed_guarantor: BTreeMap<AccountRef, DateIndex>,
+    by_created_date: DateIndex,
+    by_expiration_date: DateIndex,
+}
+
+impl Index {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<&IndexEntry> {
+        self.entries.get(hash)
+    }
+
+    pub fn insert(&mut self, hash: Hash, entry: IndexEntry) {
+        self.remove(&hash);
+
+        let created_date = entry.created_date;
+        insert_date(
+            self.by_guarantor.entry(entry.guarantor).or_default(),
+            created_date,
+            hash,
+        );
+        insert_date(
+            self.by_guarantee.entry(entry.guarantee).or_default(),
+            created_date,
+            hash,
+        );
+        if let Some(account) = entry.signed_guarantor {
+            insert_date(
+                self.by_signed_guarantor.entry(account).or_default(),
+                created_date,
+                hash,
+            );
+        }
+        insert_date(&mut self.by_created_date, created_date, hash);
+        if let Some(date) = entry.expiration_date {
+            insert_date(&mut self.by_expiration_date, date, hash);
+        }
+        self.entries.insert(hash, entry);
+    }
+
+    pub fn remove(&mut self, hash: &Hash) -> Option<IndexEntry> {
+        let entry = self.entries.remove(hash)?;
+
+        let created_date = entry.created_date;
+        for (index, account) in [
+            (&mut self.by_guarantor, Some(entry.guarantor)),
+            (&mut self.by_guarantee, Some(entry.guarantee)),
+            (&mut self.by_signed_guarantor, entry.signed_guarantor),
+        ] {
+            if let Some(account) = account {
+                if let Some(dates) = index.get_mut(&account) {
+                    remove_date(dates, created_date, hash);
+                    if dates.is_empty() {
+                        index.remove(&account);
+                    }
+                }
+            }
+        }
+        remove_date(&mut self.by_created_date, created_date, hash);
+        if let Some(date) = entry.expiration_date {
+            remove_date(&mut self.by_expiration_date, date, hash);
+        }
+        Some(entry)
+    }
+
+    /// Returns the matched hashes, ordered by the created date.
+    pub fn query(&self, query: &Query) -> Vec<Hash> {
+        let (start, end) = query.created_range();
+        if start >= end {
+            return Vec::new();
+        }
+
+        // scan the most selective index
+        let by_account = |index: &BTreeMap<AccountRef, DateIndex>, account| -> Vec<Hash> {
+            index
+                .get(&account)
+                .map(|dates| range_dates(dates, start, end).copied().collect())
+                .unwrap_or_default()
+        };
+        let mut hashes: Vec<_> = if let Some(account) = query.guarantor {
+            by_account(&self.by_guarantor, account)
+        } else if let Some(account) = query.guarantee {
+            by_account(&self.by_guarantee, account)
+        } else if let Some(account) = query.signed_guarantor {
+            by_account(&self.by_signed_guarantor, account)
+        } else if let Some(date) = query.expiring_before {
+            range_dates(&self.by_expiration_date, DateTime::MIN_DATETIME, date)
+                .copied()
+                .collect()
+        } else {
+            range_dates(&self.by_created_date, start, end)
+                .copied()
+                .collect()
+        };
+        hashes.retain(|hash| query.matches(&self.entries[hash]));
+
+        hashes.sort_by_key(|hash| (self.entries[hash].created_date, *hash));
+        hashes
+    }
+
+    /// Returns the hashes expired at the date.
+    pub fn expired(&self, now: DateTime) -> Vec<Hash> {
+        range_dates(&self.by_expiration_date, DateTime::MIN_DATETIME, now)
+            .copied()
+            .collect()
+    }
+}
+
+/// A [`Store`] maintaining the secondary [`Index`] of the stored envelopes.
+///
+/// The expired envelopes are pruned automatically on opening and on every write,
+/// and are hidden from the reads in between.
+/// [`IndexedStore::prune_expired`] prunes them on demand.
+#[derive(Debug)]
+pub struct IndexedStore<S> {
+    store: S,
+    index: RwLock<Index>,
+}
+
+impl<S> IndexedStore<S>
+where
+    S: Store,
+{
+    /// Indexes the envelopes already stored, which should be all of `Data<M, R>`,
+    /// pruning the expired ones.
+    pub fn open<M, R>(store: S) -> Result<Self>
+    where
+        M: Verifier + Indexed + Archive,
+        <M as Archive>::Archived:
+            ::core::fmt::Debug + PartialEq + Deserialize<M, SharedDeserializeMap>,
+        R: IsSigned + Archive,
+        <R as Archive>::Archived: ::core::fmt::Debug + PartialEq,
+        ArchivedData<M, R>: for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        let mut index = Index::new();
+        for hash in store.list()? {
+            let object = store
+                .get(&hash)?
+                .ok_or_else(|| anyhow!("object removed while indexing: {}", hash.to_string()))?;
+
+            // note: only the metadata is deserialized
+            let metadata: M = object
+                .archived::<Data<M, R>>()?
+                .metadata
+                .deserialize(&mut SharedDeserializeMap::default())
+                .map_err(|e| anyhow!("failed to deserialize the metadata: {e}"))?;
+            index.insert(hash, metadata.to_index_entry());
+        }
+
+        let store = Self {
+            store,
+            index: RwLock::new(index),
+        };
+        store.prune_expired(DateTime::now())?;
+        Ok(store)
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Verifies, stores and indexes the data, rejecting the expired ones
+    /// and pruning the others expired.
+    ///
+    /// See [`Store::put`] for the payloads which are not checked here.
+    pub fn put<M, R>(&self, data: &Data<M, R>) -> Result<Hash>
+    where
+        M: Verifier + Indexed + AsRef<Metadata>,
+        R: IsSigned + Serialize<SignatureSerializer>,
+        Data<M, R>: Serialize<Serializer>,
+    {
+        self.put_with(data.metadata.to_index_entry(), || self.store.put(data))
+    }
+
+    /// Verifies, stores and indexes the data signed over the DAG-CBOR bytes,
+    /// rejecting the expired ones.
+    pub fn put_dag_cbor<M, R>(&self, data: &Data<M, R>) -> Result<Hash>
+    where
+        M: Verifier + Indexed + AsRef<Metadata>,
+        R: IsSigned + ToIpld,
+        Data<M, R>: Serialize<Serializer>,
+    {
+        self.put_with(data.metadata.to_index_entry(), || {
+            self.store.put_dag_cbor(data)
+        })
+    }
+
+    fn put_with(&self, entry: IndexEntry, put: impl FnOnce() -> Result<Hash>) -> Result<Hash> {
+        let now = DateTime::now();
+        if entry.is_expired(&now) {
+            bail!("expired data");
+        }
+
+        let hash = put()?;
+        let mut index = self.index.write().map_err(|_| anyhow!("poisoned index"))?;
+        index.insert(hash, entry);
+        self.prune_expired_locked(&mut index, now)?;
+        Ok(hash)
+    }
+
+    pub fn get_data<M, R>(&self, hash: &Hash) -> Result<Option<Data<M, R>>>
+    where
+        M: Verifier,
+        R: IsSigned,
+        Data<M, R>: Archive,
+        <Data<M, R> as Archive>::Archived: for<'a> CheckBytes<DefaultValidator<'a>>
+            + Deserialize<Data<M, R>, SharedDeserializeMap>,
+    {
+        if self.is_expired(hash, DateTime::now())? {
+            return Ok(None);
+        }
+        self.store.get_data(hash)
+    }
+
+    fn is_expired(&self, hash: &Hash, now: DateTime) -> Result<bool> {
+        let index = self.index.read().map_err(|_| anyhow!("poisoned index"))?;
+        Ok(matches!(index.get(hash), Some(entry) if entry.is_expired(&now)))
+    }
+
+    pub fn get_entry(&self, hash: &Hash) -> Result<Option<IndexEntry>> {
+        let now = DateTime::now();
+        Ok(self
+            .index
+            .read()
+            .map_err(|_| anyhow!("poisoned index"))?
+            .get(hash)
+            .filter(|entry| !entry.is_expired(&now))
+            .copied())
+    }
+
+    /// Returns whether the envelope was stored.
+    pub fn remove(&self, hash: &Hash) -> Result<bool> {
+        self.index
+            .write()
+            .map_err(|_| anyhow!("poisoned index"))?
+            .remove(hash);
+        self.store.remove(hash)
+    }
+
+    /// Returns the matched hashes, ordered by the created date.
+    pub fn query(&self, query: &Query) -> Result<Vec<Hash>> {
+        let now = DateTime::now();
+        let index = self.index.read().map_err(|_| anyhow!("poisoned index"))?;
+
+        let mut hashes = index.query(query);
+        hashes.retain(|hash| !matches!(index.get(hash), Some(entry) if entry.is_expired(&now)));
+        Ok(hashes)
+    }
+
+    /// Removes the envelopes expired at the date, returning the removed hashes.
+    pub fn prune_expired(&self, now: DateTime) -> Result<Vec<Hash>> {
+        let mut index = self.index.write().map_err(|_| anyhow!("poisoned index"))?;
+        self.prune_expired_locked(&mut index, now)
+    }
+
+    fn prune_expired_locked(&self, index: &mut Index, now: DateTime) -> Result<Vec<Hash>> {
+        let expired = index.expired(now);
+        for hash in &expired {
+            self.store.remove(hash)?;
+            index.remove(hash);
+        }
+        Ok(expired)
+    }
+
+    /// Removes all envelopes but the live ones, returning the removed hashes.
+    pub fn gc(&self, live: &BTreeSet<Hash>) -> Result<Vec<Hash>> {
+        let mut index = self.index.write().map_err(|_| anyhow!("poisoned index"))?;
+
+        let removed = self.store.gc(live)?;
+        for hash in &removed {
+            index.remove(hash);
+        }
+        Ok(removed)
+    }
+}
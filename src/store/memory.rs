@@ -0,0 +1,72 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{anyhow, Result};
+use rkyv::AlignedVec;
+
+use super::{Object, ObjectBytes, Store};
+use crate::value::hash::Hash;
+
+/// An in-memory store, mainly for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    objects: RwLock<BTreeMap<Hash, Arc<AlignedVec>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Store for MemoryStore {
+    fn put_unchecked(&self, hash: &Hash, bytes: &[u8]) -> Result<()> {
+        let mut object = AlignedVec::with_capacity(bytes.len());
+        object.extend_from_slice(bytes);
+
+        self.objects
+            .write()
+            .map_err(|_| anyhow!("poisoned memory store"))?
+            .insert(*hash, Arc::new(object));
+        Ok(())
+    }
+
+    fn get(&self, hash: &Hash) -> Result<Option<Object>> {
+        Ok(self
+            .objects
+            .read()
+            .map_err(|_| anyhow!("poisoned memory store"))?
+            .get(hash)
+            .cloned()
+            .map(|bytes| Object(ObjectBytes::Owned(bytes))))
+    }
+
+    fn contains(&self, hash: &Hash) -> Result<bool> {
+        Ok(self
+            .objects
+            .read()
+            .map_err(|_| anyhow!("poisoned memory store"))?
+            .contains_key(hash))
+    }
+
+    fn remove(&self, hash: &Hash) -> Result<bool> {
+        Ok(self
+            .objects
+            .write()
+            .map_err(|_| anyhow!("poisoned memory store"))?
+            .remove(hash)
+            .is_some())
+    }
+
+    fn list(&self) -> Result<Vec<Hash>> {
+        Ok(self
+            .objects
+            .read()
+            .map_err(|_| anyhow!("poisoned memory store"))?
+            .keys()
+            .copied()
+            .collect())
+    }
+}
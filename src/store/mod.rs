@@ -0,0 +1,174 @@
+pub mod fs;
+pub mod index;
+pub mod memory;
+
+use std::{collections::BTreeSet, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use bytecheck::CheckBytes;
+use memmap2::Mmap;
+use rkyv::{
+    de::deserializers::SharedDeserializeMap, validation::validators::DefaultValidator, AlignedVec,
+    Archive, Deserialize, Serialize,
+};
+
+pub use self::{
+    fs::FsStore,
+    index::{IndexedStore, Query},
+    memory::MemoryStore,
+};
+use crate::{
+    account::Verifier,
+    data::Data,
+    ipld::ToIpld,
+    metadata::Metadata,
+    signature::SignatureSerializer,
+    signed::{IsSigned, Serializer, SERIALIZER_HEAP_SIZE},
+    value::hash::Hash,
+};
+
+/// The archived bytes of a stored object, aligned for the zero-copy access.
+pub struct Object(ObjectBytes);
+
+enum ObjectBytes {
+    Mapped(Mmap),
+    Owned(Arc<AlignedVec>),
+}
+
+impl ::core::ops::Deref for Object {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.0 {
+            ObjectBytes::Mapped(bytes) => bytes,
+            ObjectBytes::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl Object {
+    pub fn archived<T>(&self) -> Result<&<T as Archive>::Archived>
+    where
+        T: Archive,
+        <T as Archive>::Archived: for<'a> CheckBytes<DefaultValidator<'a>>,
+    {
+        ::rkyv::check_archived_root::<T>(self).map_err(|e| anyhow!("malformed object: {e}"))
+    }
+
+    pub fn deserialize<T>(&self) -> Result<T>
+    where
+        T: Archive,
+        <T as Archive>::Archived:
+            for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<T, SharedDeserializeMap>,
+    {
+        self.archived::<T>()?
+            .deserialize(&mut SharedDeserializeMap::default())
+            .map_err(|e| anyhow!("failed to deserialize the object: {e}"))
+    }
+}
+
+/// A content-addressed store of the archived [`Data`], keyed by the [`Hash`] of the archived bytes.
+///
+/// The typed methods are generic, so they are unavailable on `dyn Store`,
+/// which is left with the byte-level ones.
+pub trait Store {
+    /// Stores the archived bytes under the hash as is, replacing them atomically.
+    ///
+    /// Prefer [`Store::put`], which verifies the data before storing.
+    fn put_unchecked(&self, hash: &Hash, bytes: &[u8]) -> Result<()>;
+
+    fn get(&self, hash: &Hash) -> Result<Option<Object>>;
+
+    fn contains(&self, hash: &Hash) -> Result<bool>;
+
+    /// Returns whether the object was stored.
+    fn remove(&self, hash: &Hash) -> Result<bool>;
+
+    fn list(&self) -> Result<Vec<Hash>>;
+
+    /// Verifies the signatures and the payload hash,
+    /// and stores the archived data, returning its hash.
+    ///
+    /// The payload hash of [`Commitment::Plaintext`] is not checked here, as it needs the key;
+    /// [`Data::open`] checks it instead. The data signed over the DAG-CBOR bytes should be
+    /// stored with [`Store::put_dag_cbor`].
+    ///
+    /// [`Commitment::Plaintext`]: crate::sealed::Commitment::Plaintext
+    /// [`Data::open`]: crate::data::Data::open
+    fn put<M, R>(&self, data: &Data<M, R>) -> Result<Hash>
+    where
+        Self: Sized,
+        M: Verifier + AsRef<Metadata>,
+        R: IsSigned + Serialize<SignatureSerializer>,
+        Data<M, R>: Serialize<Serializer>,
+    {
+        data.verify(None)?;
+        if let Some(hash) = data.data.to_payload_hash()? {
+            if hash != data.metadata.as_ref().hash {
+                bail!("payload hash mismatching");
+            }
+        }
+        put_archived(self, data)
+    }
+
+    /// Verifies the signatures and the payload hash over the DAG-CBOR bytes,
+    /// and stores the archived data, returning its hash.
+    fn put_dag_cbor<M, R>(&self, data: &Data<M, R>) -> Result<Hash>
+    where
+        Self: Sized,
+        M: Verifier + AsRef<Metadata>,
+        R: IsSigned + ToIpld,
+        Data<M, R>: Serialize<Serializer>,
+    {
+        data.verify_dag_cbor(None)?;
+        if Hash::with_dag_cbor(&data.data.to_dag_cbor()?) != data.metadata.as_ref().hash {
+            bail!("payload hash mismatching");
+        }
+        put_archived(self, data)
+    }
+
+    fn get_data<M, R>(&self, hash: &Hash) -> Result<Option<Data<M, R>>>
+    where
+        Self: Sized,
+        M: Verifier,
+        R: IsSigned,
+        Data<M, R>: Archive,
+        <Data<M, R> as Archive>::Archived: for<'a> CheckBytes<DefaultValidator<'a>>
+            + Deserialize<Data<M, R>, SharedDeserializeMap>,
+    {
+        self.get(hash)?
+            .map(|object| object.deserialize())
+            .transpose()
+    }
+
+    /// Removes all objects but the live ones, returning the removed hashes.
+    fn gc(&self, live: &BTreeSet<Hash>) -> Result<Vec<Hash>> {
+        collect_garbage(self, live)
+    }
+}
+
+fn put_archived<S, T>(store: &S, data: &T) -> Result<Hash>
+where
+    S: Store + ?Sized,
+    T: Serialize<Serializer>,
+{
+    let bytes = ::rkyv::to_bytes::<_, SERIALIZER_HEAP_SIZE>(data)?;
+    let hash = Hash::with_bytes(&bytes);
+    if !store.contains(&hash)? {
+        store.put_unchecked(&hash, &bytes)?;
+    }
+    Ok(hash)
+}
+
+fn collect_garbage<S>(store: &S, live: &BTreeSet<Hash>) -> Result<Vec<Hash>>
+where
+    S: Store + ?Sized,
+{
+    let mut removed = Vec::new();
+    for hash in store.list()? {
+        if !live.contains(&hash) && store.remove(&hash)? {
+            removed.push(hash);
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Result};
+use bytecheck::CheckBytes;
+use chrono::Duration;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::{
+    account::{Account, AccountRef, Identity, Signer, Verifier},
+    metadata::Metadata,
+    value::{chrono::DateTime, hash::Hash},
+};
+
+/// The claim that the metadata existed at the time.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct Timestamp {
+    /// The hash of the archived [`Metadata`]
+    pub hash: Hash,
+    pub time: DateTime,
+}
+
+impl Timestamp {
+    pub fn new(metadata: &Metadata, time: DateTime) -> Result<Self> {
+        Ok(Self {
+            hash: hash_metadata(metadata)?,
+            time,
+        })
+    }
+}
+
+fn hash_metadata(metadata: &Metadata) -> Result<Hash> {
+    ::rkyv::to_bytes::<_, 64>(metadata)
+        .map(|bytes| Hash::with_bytes(&bytes))
+        .map_err(Into::into)
+}
+
+/// The [`Timestamp`] countersigned by a timestamp authority, as RFC 3161 but native to `ipi`.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct TimestampSigned {
+    pub authority: Identity,
+    pub data: Timestamp,
+}
+
+impl ::core::ops::Deref for TimestampSigned {
+    type Target = Timestamp;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+impl Signer<Timestamp> for TimestampSigned {
+    fn sign(account: &Account, data: Timestamp) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            authority: account.sign(&data)?,
+            data,
+        })
+    }
+}
+
+/// The `guarantor` stands for the expected authority here.
+impl Verifier for TimestampSigned {
+    fn verify(&self, authority: Option<&AccountRef>) -> Result<()> {
+        if let Some(authority) = authority {
+            if &self.authority.account != authority {
+                bail!("timestamp authority mismatching");
+            }
+        }
+
+        self.authority.verify(&self.data)
+    }
+}
+
+pub trait TimestampAuthority {
+    fn account_ref(&self) -> AccountRef;
+
+    /// Attests that the metadata exists now.
+    fn timestamp(&self, metadata: &Metadata) -> Result<TimestampSigned>;
+}
+
+/// An in-process authority trusting its own clock, mainly for tests.
+pub struct LocalTimestampAuthority {
+    account: Account,
+}
+
+impl LocalTimestampAuthority {
+    pub fn new(account: Account) -> Self {
+        Self { account }
+    }
+
+    pub fn generate() -> Self {
+        Self::new(Account::generate())
+    }
+
+    /// Attests the given time instead of the current one, such as to test the policies.
+    pub fn timestamp_at(&self, metadata: &Metadata, time: DateTime) -> Result<TimestampSigned> {
+        Signer::sign(&self.account, Timestamp::new(metadata, time)?)
+    }
+}
+
+impl TimestampAuthority for LocalTimestampAuthority {
+    fn account_ref(&self) -> AccountRef {
+        self.account.account_ref()
+    }
+
+    fn timestamp(&self, metadata: &Metadata) -> Result<TimestampSigned> {
+        self.timestamp_at(metadata, DateTime::now())
+    }
+}
+
+/// Which timestamp attestations to trust, and how far `created_date` may be from them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimestampPolicy {
+    authorities: BTreeSet<AccountRef>,
+    required: bool,
+    max_clock_skew: Duration,
+    max_delay: Duration,
+}
+
+impl Default for TimestampPolicy {
+    fn default() -> Self {
+        Self {
+            authorities: Default::default(),
+            required: false,
+            max_clock_skew: Duration::minutes(1),
+            max_delay: Duration::minutes(5),
+        }
+    }
+}
+
+impl TimestampPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn trust(mut self, authority: AccountRef) -> Self {
+        self.authorities.insert(authority);
+        self
+    }
+
+    /// Rejects the metadata without any attestation of the trusted authorities.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// How much later `created_date` may be than the attested time.
+    pub fn max_clock_skew(mut self, duration: Duration) -> Self {
+        self.max_clock_skew = duration;
+        self
+    }
+
+    /// How much later the attested time may be than `created_date`.
+    pub fn max_delay(mut self, duration: Duration) -> Self {
+        self.max_delay = duration;
+        self
+    }
+
+    /// Verifies the attestations of the trusted authorities, ignoring the others.
+    ///
+    /// The signatures of the metadata itself are not verified here.
+    pub fn verify(&self, metadata: &Metadata, attestations: &[TimestampSigned]) -> Result<()> {
+        let hash = hash_metadata(metadata)?;
+
+        let mut attested = false;
+        for attestation in attestations
+            .iter()
+            .filter(|attestation| self.authorities.contains(&attestation.authority.account))
+        {
+            attestation.verify(None)?;
+            if attestation.hash != hash {
+                bail!("timestamp of the other metadata");
+            }
+
+            // note: the bounds beyond the representable dates are unbounded
+            let created_date = metadata.created_date.0;
+            let time = attestation.time.0;
+            if matches!(
+                time.checked_add_signed(self.max_clock_skew),
+                Some(bound) if created_date > bound,
+            ) {
+                bail!("created after the attested time: {created_date} > {time}");
+            }
+            if matches!(
+                created_date.checked_add_signed(self.max_delay),
+                Some(bound) if time > bound,
+            ) {
+                bail!("attested too late after created: {time} > {created_date}");
+            }
+            attested = true;
+        }
+
+        if self.required && !attested {
+            bail!("no trusted timestamp attestation");
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
-use bytecheck::CheckBytes;
-use ndarray::{Dim, Dimension, Ix, IxDyn};
-use rkyv::{Archive, Deserialize, Fallible, Serialize};
+use bytecheck::{CheckBytes, StructCheckError};
+use ndarray::{Dim, Dimension, ErrorKind, IntoDimension, Ix, IxDyn, ShapeError};
+use rkyv::{vec::ArchivedVec, Archive, Deserialize, Fallible, Infallible, Serialize};
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, ::serde::Serialize, ::serde::Deserialize)]
 pub struct Array<A, D>(pub ::ndarray::ArcArray<A, D>)
@@ -28,6 +28,22 @@ where
     }
 }
 
+impl<A, D> Array<A, D>
+where
+    D: Dimension,
+{
+    /// Copies the elements in the logical order, whatever the memory layout is.
+    fn to_standard_vec(&self) -> Vec<A>
+    where
+        A: Clone,
+    {
+        match self.0.as_slice() {
+            Some(data) => data.to_vec(),
+            None => self.0.iter().cloned().collect(),
+        }
+    }
+}
+
 impl<A, const D: usize> Array<A, Dim<[Ix; D]>>
 where
     Dim<[Ix; D]>: Dimension,
@@ -39,18 +55,14 @@ where
             .map(|e| Self(e))
     }
 
-    fn try_to_raw(&self) -> Result<ArrayRaw<A, <Dim<[Ix; D]> as Dimension>::Pattern>>
+    fn to_raw(&self) -> ArrayRaw<A, <Dim<[Ix; D]> as Dimension>::Pattern>
     where
         A: Clone,
     {
-        Ok(ArrayRaw {
-            data: self
-                .0
-                .as_slice()
-                .ok_or_else(|| ::anyhow::anyhow!("data is not standard layout"))?
-                .to_vec(),
+        ArrayRaw {
+            data: self.to_standard_vec(),
             dim: self.0.dim(),
-        })
+        }
     }
 }
 
@@ -68,7 +80,7 @@ where
 
     #[inline]
     unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
-        self.try_to_raw().unwrap().resolve(pos, resolver, out)
+        self.to_raw().resolve(pos, resolver, out)
     }
 }
 
@@ -83,7 +95,7 @@ where
 {
     #[inline]
     fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        self.try_to_raw().unwrap().serialize(serializer)
+        self.to_raw().serialize(serializer)
     }
 }
 
@@ -100,12 +112,14 @@ where
 {
     #[inline]
     fn deserialize(&self, deserializer: &mut De) -> Result<Array<A, Dim<[Ix; D]>>, De::Error> {
+        // note: the shape is proven to match the data by `CheckBytes`,
+        //       unless the caller broke the contract of `rkyv::archived_root`,
+        //       so that this cannot panic in safe code
         Deserialize::<ArrayRaw<A, <Dim<[Ix; D]> as Dimension>::Pattern>, De>::deserialize(
             self,
             deserializer,
         )
-        // FIXME: handle ndarray errors
-        .map(|e| Array::try_from_raw(e).unwrap())
+        .map(|e| Array::try_from_raw(e).expect("malformed archived array"))
     }
 }
 
@@ -117,18 +131,14 @@ impl<A> Array<A, IxDyn> {
             .map(|e| Self(e))
     }
 
-    fn try_to_raw_dyn(&self) -> Result<ArrayRaw<A, Vec<usize>>>
+    fn to_raw_dyn(&self) -> ArrayRaw<A, Vec<usize>>
     where
         A: Clone,
     {
-        Ok(ArrayRaw {
-            data: self
-                .0
-                .as_slice()
-                .ok_or_else(|| ::anyhow::anyhow!("data is not standard layout"))?
-                .to_vec(),
+        ArrayRaw {
+            data: self.to_standard_vec(),
             dim: self.0.shape().to_vec(),
-        })
+        }
     }
 }
 
@@ -143,7 +153,7 @@ where
 
     #[inline]
     unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
-        self.try_to_raw_dyn().unwrap().resolve(pos, resolver, out)
+        self.to_raw_dyn().resolve(pos, resolver, out)
     }
 }
 
@@ -156,7 +166,7 @@ where
 {
     #[inline]
     fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        self.try_to_raw_dyn().unwrap().serialize(serializer)
+        self.to_raw_dyn().serialize(serializer)
     }
 }
 
@@ -169,9 +179,30 @@ where
 {
     #[inline]
     fn deserialize(&self, deserializer: &mut De) -> Result<Array<A, IxDyn>, De::Error> {
+        // note: the shape is proven to match the data by `CheckBytes`
         Deserialize::<ArrayRaw<A, Vec<usize>>, De>::deserialize(self, deserializer)
-            // FIXME: handle ndarray errors
-            .map(|e| Array::try_from_raw_dyn(e).unwrap())
+            .map(|e| Array::try_from_raw_dyn(e).expect("malformed archived array"))
+    }
+}
+
+impl<A> PartialEq<Array<A, IxDyn>> for <Array<A, IxDyn> as Archive>::Archived
+where
+    A: Clone + Archive,
+    <A as Archive>::Archived: ::core::fmt::Debug + PartialEq + PartialEq<A>,
+{
+    fn eq(&self, other: &Array<A, IxDyn>) -> bool {
+        self.dim.len() == other.ndim()
+            && self
+                .dim
+                .iter()
+                .zip(other.shape())
+                .all(|(&archived, &len)| archived.value() as usize == len)
+            && self.data.len() == other.len()
+            && self
+                .data
+                .iter()
+                .zip(other.iter())
+                .all(|(archived, value)| archived == value)
     }
 }
 
@@ -191,12 +222,77 @@ where
     <D as Archive>::Archived: ::core::fmt::Debug + PartialEq,
 ",))]
 #[archive(compare(PartialEq))]
-#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+#[archive_attr(derive(Debug, PartialEq))]
 pub struct ArrayRaw<A, D> {
     data: Vec<A>,
     dim: D,
 }
 
+impl<A, D, C> CheckBytes<C> for ArchivedArrayRaw<A, D>
+where
+    A: Archive,
+    ArchivedVec<<A as Archive>::Archived>: CheckBytes<C> + ::core::fmt::Debug + PartialEq,
+    D: Archive + IntoDimension,
+    <D as Archive>::Archived:
+        CheckBytes<C> + Deserialize<D, Infallible> + ::core::fmt::Debug + PartialEq,
+    C: ?Sized,
+{
+    type Error = StructCheckError;
+
+    unsafe fn check_bytes<'a>(
+        value: *const Self,
+        context: &mut C,
+    ) -> Result<&'a Self, Self::Error> {
+        fn field_error(
+            field_name: &'static str,
+            inner: impl ::std::error::Error + 'static,
+        ) -> StructCheckError {
+            StructCheckError {
+                field_name,
+                inner: Box::new(inner),
+            }
+        }
+
+        let data = ArchivedVec::<<A as Archive>::Archived>::check_bytes(
+            ::core::ptr::addr_of!((*value).data),
+            context,
+        )
+        .map_err(|error| field_error("data", error))?;
+        <D as Archive>::Archived::check_bytes(::core::ptr::addr_of!((*value).dim), context)
+            .map_err(|error| field_error("dim", error))?;
+
+        // the shape should cover the data exactly, as `from_shape_vec` requires
+        let value = &*value;
+        let dim = match value.dim.deserialize(&mut Infallible) {
+            Ok(dim) => dim.into_dimension(),
+            Err(error) => match error {},
+        };
+        if !is_valid_shape(dim.slice(), data.len()) {
+            return Err(field_error(
+                "dim",
+                ShapeError::from_kind(ErrorKind::IncompatibleShape),
+            ));
+        }
+        Ok(value)
+    }
+}
+
+/// Checks whether the shape covers the given number of elements, as `ndarray` requires.
+fn is_valid_shape(shape: &[usize], len: usize) -> bool {
+    // note: the product of the non-zero axis lengths should not exceed `isize::MAX`
+    let size = shape
+        .iter()
+        .filter(|&&axis| axis != 0)
+        .try_fold(1usize, |size, &axis| size.checked_mul(axis))
+        .filter(|&size| size <= isize::MAX as usize);
+
+    match size {
+        Some(_) if shape.contains(&0) => len == 0,
+        Some(size) => len == size,
+        None => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -247,4 +343,51 @@ mod tests {
         // verify
         assert_eq!(&tensor, &deserialized);
     }
+    #[test]
+    fn test_array_rkyv_layout() {
+        // transpose the matrix, which is no longer in the standard layout
+        let matrix = ::ndarray::ArcArray::from_shape_vec((2, 3), vec![1, 2, 3, 4, 5, 6])
+            .unwrap()
+            .reversed_axes();
+        assert!(matrix.as_slice().is_none());
+
+        let array = Array(matrix.clone());
+        let bytes = ::rkyv::to_bytes::<_, 256>(&array).unwrap();
+        let deserialized: Array<i64, ndarray::Ix2> = ::rkyv::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized, array);
+
+        let array = Array(matrix.into_dyn());
+        let bytes = ::rkyv::to_bytes::<_, 256>(&array).unwrap();
+        let deserialized: Array<i64, IxDyn> = ::rkyv::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized, array);
+    }
+
+    #[test]
+    fn test_array_malformed_shape() {
+        // the shape should cover the data exactly
+        let raw = ArrayRaw {
+            data: vec![0u64; 5],
+            dim: (2usize, 3usize),
+        };
+        let bytes = ::rkyv::to_bytes::<_, 256>(&raw).unwrap();
+        assert!(::rkyv::check_archived_root::<Array<u64, ndarray::Ix2>>(&bytes).is_err());
+
+        // note: the archived lengths are 32 bits wide
+        let axis = u32::MAX as usize;
+        for dim in [vec![2, 3], vec![0, axis, axis, axis]] {
+            let raw = ArrayRaw {
+                data: Vec::<u64>::new(),
+                dim,
+            };
+            let bytes = ::rkyv::to_bytes::<_, 256>(&raw).unwrap();
+            assert!(::rkyv::check_archived_root::<Array<u64, IxDyn>>(&bytes).is_err());
+        }
+
+        let raw = ArrayRaw {
+            data: vec![0u64; 6],
+            dim: vec![2, 3],
+        };
+        let bytes = ::rkyv::to_bytes::<_, 256>(&raw).unwrap();
+        assert!(::rkyv::check_archived_root::<Array<u64, IxDyn>>(&bytes).is_ok());
+    }
 }
@@ -0,0 +1,244 @@
+use anyhow::{anyhow, bail, Result};
+use base58::FromBase58;
+use fixed::types::U0F32;
+
+use super::{
+    bytes::Bytes, chrono::DateTime, text::Text, unit_interval::UnitInterval, Value, ValueType,
+};
+
+impl Value {
+    /// Parses the string as the given type.
+    ///
+    /// The string should be in the form of [`ToString`], e.g. bytes in base58 and dates in
+    /// RFC 3339. The `Dyn` type infers the value, trying `None`, `Bool`, `I64`, `F64`,
+    /// and then `Text` in order.
+    pub fn parse(s: &str, ty: ValueType) -> Result<Self> {
+        let trimmed = s.trim();
+
+        match ty {
+            ValueType::None => match trimmed {
+                "()" => Ok(Self::None),
+                _ => bail!("failed to parse None: {s:?}"),
+            },
+            ValueType::Dyn => Ok([
+                ValueType::None,
+                ValueType::Bool,
+                ValueType::I64,
+                ValueType::F64,
+            ]
+            .into_iter()
+            .find_map(|ty| Self::parse(s, ty).ok())
+            .unwrap_or_else(|| Self::Text(Text::with_en_us(s)))),
+            ValueType::Bool => trimmed.parse().map(Self::Bool).map_err(Into::into),
+            ValueType::I8 => trimmed.parse().map(Self::I8).map_err(Into::into),
+            ValueType::I16 => trimmed.parse().map(Self::I16).map_err(Into::into),
+            ValueType::I32 => trimmed.parse().map(Self::I32).map_err(Into::into),
+            ValueType::I64 => trimmed.parse().map(Self::I64).map_err(Into::into),
+            ValueType::U8 => trimmed.parse().map(Self::U8).map_err(Into::into),
+            ValueType::U16 => trimmed.parse().map(Self::U16).map_err(Into::into),
+            ValueType::U32 => trimmed.parse().map(Self::U32).map_err(Into::into),
+            ValueType::U64 => trimmed.parse().map(Self::U64).map_err(Into::into),
+            ValueType::F32 => trimmed.parse().map(Self::F32).map_err(Into::into),
+            ValueType::F64 => trimmed.parse().map(Self::F64).map_err(Into::into),
+            ValueType::Bytes => trimmed
+                .from_base58()
+                .map(|bytes| Self::Bytes(Bytes(bytes)))
+                .map_err(|_| anyhow!("failed to parse Bytes: {s:?}")),
+            ValueType::Text => Ok(Self::Text(Text::with_en_us(s))),
+            ValueType::Hash => trimmed.parse().map(Self::Hash),
+            ValueType::DateTime => ::chrono::DateTime::parse_from_rfc3339(trimmed)
+                .map(|date| Self::DateTime(DateTime(date.with_timezone(&::chrono::Utc))))
+                .map_err(Into::into),
+            ValueType::Uuid => trimmed.parse().map(Self::Uuid).map_err(Into::into),
+            ValueType::UnitInterval => trimmed
+                .parse::<f64>()
+                .map_err(Into::into)
+                .and_then(to_unit_interval),
+            ValueType::List
+            | ValueType::Map
+            | ValueType::ArrayU8
+            | ValueType::ArrayI64
+            | ValueType::ArrayF32
+            | ValueType::ArrayF64 => bail!("cannot parse {ty:?} from string"),
+        }
+    }
+
+    /// Converts the value into the given type.
+    ///
+    /// * Integers are converted losslessly, or fail on overflow.
+    /// * Floats are truncated toward zero into integers, or fail on NaN, infinity and overflow.
+    /// * Integers into floats and `F64` into `F32` are rounded to the nearest.
+    /// * `Bool` is converted from and into `0` and `1`.
+    /// * `Text` is converted into `Bytes` as UTF-8, and `Bytes` into `Text` if valid UTF-8.
+    /// * `Text` is converted into the other types with [`Value::parse`].
+    /// * The other scalar types are converted into `Text` with [`ToString`].
+    /// * Every value is kept as is into `Dyn`.
+    pub fn cast(self, ty: ValueType) -> Result<Self> {
+        let from = self.value_type();
+        if from == ty || ty == ValueType::Dyn {
+            return Ok(self);
+        }
+
+        match (self, ty) {
+            // text
+            (Self::Text(value), ValueType::Bytes) => Ok(Self::Bytes(Bytes(value.msg.into_bytes()))),
+            (Self::Bytes(value), ValueType::Text) => String::from_utf8(value.0)
+                .map(|msg| Self::Text(Text::with_en_us(msg)))
+                .map_err(|_| anyhow!("cannot cast non-UTF-8 Bytes to Text")),
+            (Self::Text(value), ty) => Self::parse(&value.msg, ty),
+            (
+                value @ (Self::None
+                | Self::Bool(_)
+                | Self::I8(_)
+                | Self::I16(_)
+                | Self::I32(_)
+                | Self::I64(_)
+                | Self::U8(_)
+                | Self::U16(_)
+                | Self::U32(_)
+                | Self::U64(_)
+                | Self::F32(_)
+                | Self::F64(_)
+                | Self::Hash(_)
+                | Self::DateTime(_)
+                | Self::Uuid(_)
+                | Self::UnitInterval(_)),
+                ValueType::Text,
+            ) => Ok(Self::Text(Text::with_en_us(value.to_string()))),
+
+            // numbers
+            (value, ty) => match (value.to_integer(), value.to_float()) {
+                (Some(integer), _) => from_integer(integer, ty),
+                (None, Some(float)) => from_float(float, ty),
+                (None, None) => None,
+            }
+            .unwrap_or_else(|| Err(anyhow!("cannot cast {from:?} to {ty:?}"))),
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::None => ValueType::None,
+            Self::Dyn => ValueType::Dyn,
+            Self::Bool(_) => ValueType::Bool,
+            Self::I8(_) => ValueType::I8,
+            Self::I16(_) => ValueType::I16,
+            Self::I32(_) => ValueType::I32,
+            Self::I64(_) => ValueType::I64,
+            Self::U8(_) => ValueType::U8,
+            Self::U16(_) => ValueType::U16,
+            Self::U32(_) => ValueType::U32,
+            Self::U64(_) => ValueType::U64,
+            Self::F32(_) => ValueType::F32,
+            Self::F64(_) => ValueType::F64,
+            Self::Bytes(_) => ValueType::Bytes,
+            Self::Text(_) => ValueType::Text,
+            Self::Hash(_) => ValueType::Hash,
+            Self::DateTime(_) => ValueType::DateTime,
+            Self::Uuid(_) => ValueType::Uuid,
+            Self::UnitInterval(_) => ValueType::UnitInterval,
+            Self::List(_) => ValueType::List,
+            Self::Map(_) => ValueType::Map,
+            Self::ArrayU8(_) => ValueType::ArrayU8,
+            Self::ArrayI64(_) => ValueType::ArrayI64,
+            Self::ArrayF32(_) => ValueType::ArrayF32,
+            Self::ArrayF64(_) => ValueType::ArrayF64,
+        }
+    }
+
+    pub(super) fn to_integer(&self) -> Option<i128> {
+        match *self {
+            Self::Bool(value) => Some(value.into()),
+            Self::I8(value) => Some(value.into()),
+            Self::I16(value) => Some(value.into()),
+            Self::I32(value) => Some(value.into()),
+            Self::I64(value) => Some(value.into()),
+            Self::U8(value) => Some(value.into()),
+            Self::U16(value) => Some(value.into()),
+            Self::U32(value) => Some(value.into()),
+            Self::U64(value) => Some(value.into()),
+            _ => None,
+        }
+    }
+
+    pub(super) fn to_float(&self) -> Option<f64> {
+        match *self {
+            Self::F32(value) => Some(value.into()),
+            Self::F64(value) => Some(value),
+            Self::UnitInterval(value) => Some(value.to_num()),
+            _ => None,
+        }
+    }
+}
+
+fn from_integer(value: i128, ty: ValueType) -> Option<Result<Value>> {
+    fn convert<T>(value: i128, ty: ValueType, f: impl FnOnce(T) -> Value) -> Result<Value>
+    where
+        T: TryFrom<i128>,
+    {
+        T::try_from(value)
+            .map(f)
+            .map_err(|_| anyhow!("integer overflow: cannot cast {value} to {ty:?}"))
+    }
+
+    Some(match ty {
+        ValueType::Bool => match value {
+            0 => Ok(Value::Bool(false)),
+            1 => Ok(Value::Bool(true)),
+            _ => Err(anyhow!("cannot cast {value} to Bool")),
+        },
+        ValueType::I8 => convert(value, ty, Value::I8),
+        ValueType::I16 => convert(value, ty, Value::I16),
+        ValueType::I32 => convert(value, ty, Value::I32),
+        ValueType::I64 => convert(value, ty, Value::I64),
+        ValueType::U8 => convert(value, ty, Value::U8),
+        ValueType::U16 => convert(value, ty, Value::U16),
+        ValueType::U32 => convert(value, ty, Value::U32),
+        ValueType::U64 => convert(value, ty, Value::U64),
+        // note: i128 never exceeds the range of f32
+        ValueType::F32 => Ok(Value::F32(value as f32)),
+        ValueType::F64 => Ok(Value::F64(value as f64)),
+        ValueType::UnitInterval => to_unit_interval(value as f64),
+        _ => return None,
+    })
+}
+
+fn from_float(value: f64, ty: ValueType) -> Option<Result<Value>> {
+    Some(match ty {
+        ValueType::F32 => to_f32(value),
+        ValueType::F64 => Ok(Value::F64(value)),
+        ValueType::UnitInterval => to_unit_interval(value),
+        ValueType::Bool
+        | ValueType::I8
+        | ValueType::I16
+        | ValueType::I32
+        | ValueType::I64
+        | ValueType::U8
+        | ValueType::U16
+        | ValueType::U32
+        | ValueType::U64 => {
+            if !value.is_finite() {
+                Err(anyhow!("cannot cast {value} to {ty:?}"))
+            } else {
+                // note: out-of-range floats are saturated, and then rejected as overflow
+                from_integer(value.trunc() as i128, ty)?
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn to_f32(value: f64) -> Result<Value> {
+    // note: the non-finite values are kept as is
+    let narrowed = value as f32;
+    if value.is_finite() && !narrowed.is_finite() {
+        bail!("float overflow: cannot cast {value} to F32");
+    }
+    Ok(Value::F32(narrowed))
+}
+
+fn to_unit_interval(value: f64) -> Result<Value> {
+    U0F32::checked_from_num(value)
+        .map(|value| Value::UnitInterval(UnitInterval(value)))
+        .ok_or_else(|| anyhow!("out of the unit interval [0, 1): {value}"))
+}
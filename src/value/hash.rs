@@ -1,12 +1,31 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+};
+#[cfg(not(target_os = "wasi"))]
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{sync_channel, Receiver},
+};
 
+use anyhow::{bail, Result};
+use bytecheck::CheckBytes;
 use cid::{
-    multihash::{Code, MultihashDigest},
-    Cid,
+    multibase::Base,
+    multihash::{Code, Multihash, MultihashDigest},
+    Cid, Version,
 };
 use quick_protobuf::{MessageWrite, Writer};
 use rkyv::{Archive, Deserialize, Fallible, Serialize};
 
+mod directory;
+mod proof;
+mod verify;
+
+pub use self::{directory::DirectoryBuilder, proof::RangeProof, verify::VerifyingReader};
+
 #[derive(
     Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, ::serde::Deserialize,
 )]
@@ -36,9 +55,25 @@ impl TryFrom<&Hash> for [u8; Hash::SIZE] {
     type Error = ::cid::Error;
 
     fn try_from(hash: &Hash) -> Result<Self, Self::Error> {
-        hash.0
-            .into_v1()
-            .map(|cid| cid.to_bytes().as_slice().try_into().unwrap())
+        hash.0.into_v1().and_then(|cid| {
+            cid.to_bytes()
+                .as_slice()
+                .try_into()
+                .map_err(|_| ::cid::Error::ParsingError)
+        })
+    }
+}
+
+impl TryFrom<&[u8]> for Hash {
+    type Error = ::cid::Error;
+
+    fn try_from(mut bytes: &[u8]) -> Result<Self, Self::Error> {
+        let cid = Cid::read_bytes(&mut bytes)?;
+        if bytes.is_empty() {
+            Ok(Self(cid))
+        } else {
+            Err(::cid::Error::ParsingError)
+        }
     }
 }
 
@@ -54,39 +89,103 @@ impl ToString for Hash {
     }
 }
 
+/// The archived form of [`Hash`], storing the CID bytes inline.
+///
+/// Any valid CID fits in the buffer, so that archiving never fails.
+///
+/// The fields are private, so a value is either resolved from a [`Hash`]
+/// or validated by [`CheckBytes`]; both guarantee the bytes parse as a CID.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(C)]
+pub struct ArchivedHash {
+    len: u8,
+    bytes: [u8; Hash::MAX_SIZE],
+}
+
+impl ::core::fmt::Debug for ArchivedHash {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        match Cid::try_from(self.as_bytes()) {
+            Ok(cid) => f.debug_tuple("ArchivedHash").field(&cid).finish(),
+            Err(_) => f
+                .debug_tuple("ArchivedHash")
+                .field(&self.as_bytes())
+                .finish(),
+        }
+    }
+}
+
+impl PartialEq<Hash> for ArchivedHash {
+    fn eq(&self, other: &Hash) -> bool {
+        self.as_bytes() == other.0.to_bytes()
+    }
+}
+
+impl PartialOrd<Hash> for ArchivedHash {
+    fn partial_cmp(&self, other: &Hash) -> Option<::core::cmp::Ordering> {
+        self.as_bytes().partial_cmp(other.0.to_bytes().as_slice())
+    }
+}
+
+impl<C: ?Sized> CheckBytes<C> for ArchivedHash {
+    type Error = ::cid::Error;
+
+    unsafe fn check_bytes<'a>(value: *const Self, _: &mut C) -> Result<&'a Self, Self::Error> {
+        let value = &*value;
+        let len = value.len as usize;
+        if len > Hash::MAX_SIZE || value.bytes[len..].iter().any(|&byte| byte != 0) {
+            return Err(::cid::Error::ParsingError);
+        }
+
+        // the CID should occupy the whole buffer
+        // note: `Deserialize` relies on this to parse the bytes infallibly
+        let cid = Cid::try_from(value.as_bytes())?;
+        if cid.to_bytes().len() != len {
+            return Err(::cid::Error::ParsingError);
+        }
+        Ok(value)
+    }
+}
+
+impl ArchivedHash {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
 impl Archive for Hash {
-    type Archived = <[u8; Hash::SIZE] as Archive>::Archived;
-    type Resolver = <[u8; Hash::SIZE] as Archive>::Resolver;
+    type Archived = ArchivedHash;
+    type Resolver = ();
 
     #[inline]
-    unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
-        // FIXME: handle Cid parsing errors
-        <[u8; Hash::SIZE]>::try_from(self)
-            .unwrap()
-            .resolve(pos, resolver, out)
+    unsafe fn resolve(&self, _: usize, (): Self::Resolver, out: *mut Self::Archived) {
+        // note: the buffer is large enough for any CID
+        let cid = self.0.to_bytes();
+        let mut bytes = [0; Hash::MAX_SIZE];
+        bytes[..cid.len()].copy_from_slice(&cid);
+
+        out.write(ArchivedHash {
+            len: cid.len() as u8,
+            bytes,
+        })
     }
 }
 
-impl<S: Fallible + ?Sized> Serialize<S> for Hash
-where
-    S: ::rkyv::ser::ScratchSpace + ::rkyv::ser::Serializer,
-{
+impl<S: Fallible + ?Sized> Serialize<S> for Hash {
     #[inline]
-    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        // FIXME: handle Cid parsing errors
-        <[u8; Hash::SIZE]>::try_from(self)
-            .unwrap()
-            .serialize(serializer)
+    fn serialize(&self, _: &mut S) -> Result<Self::Resolver, S::Error> {
+        Ok(())
     }
 }
 
-impl<D: Fallible + ?Sized> Deserialize<Hash, D> for <Hash as Archive>::Archived {
+impl<D: Fallible + ?Sized> Deserialize<Hash, D> for ArchivedHash {
     #[inline]
-    fn deserialize(&self, deserializer: &mut D) -> Result<Hash, D::Error> {
-        Deserialize::<[u8; Hash::SIZE], D>::deserialize(self, deserializer)
-            // FIXME: handle Cid parsing errors
-            .map(|bytes| Cid::try_from(bytes.as_slice()).unwrap())
-            .map(Hash)
+    fn deserialize(&self, _: &mut D) -> Result<Hash, D::Error> {
+        // note: the bytes are proven to parse as a CID by `CheckBytes` or by `Archive::resolve`,
+        //       unless the caller broke the contract of `rkyv::archived_root`,
+        //       so that this cannot panic in safe code
+        Ok(Hash(
+            Cid::try_from(self.as_bytes()).expect("malformed archived hash"),
+        ))
     }
 }
 
@@ -112,42 +211,75 @@ impl Hash {
     /// DAG-PB multicodec code
     const CODEC_DAG_PB: u64 = 0x70;
 
+    /// DAG-CBOR multicodec code
+    const CODEC_DAG_CBOR: u64 = 0x71;
+
+    /// Identity multihash code
+    const MULTIHASH_IDENTITY: u64 = 0x00;
+
     /// Hash size
     const SIZE: usize = 32 + 4;
 
+    /// Max CID size: version, codec, multihash code and digest size, and the 64-byte digest
+    const MAX_SIZE: usize = 1 + 10 + 10 + 1 + 64;
+
     pub fn with_bytes(bytes: &[u8]) -> Self {
-        let level = {
-            let num_bytes = bytes.len();
-            if num_bytes <= Self::CHUNK_SIZE {
-                0
-            } else {
-                let num_chunks = (num_bytes - 1) / Self::CHUNK_SIZE + 1;
+        Self::with_bytes_sized(bytes, &Default::default()).0
+    }
 
-                let mut level = 1;
-                let mut max_chunks_per_level = Self::MAX_LINKS;
+    /// Returns the hash with the cumulative size of the DAG, including the raw data.
+    fn with_bytes_sized(bytes: &[u8], options: &HashOptions) -> (Self, u64) {
+        let level = Self::level_of(bytes.len());
 
-                while num_chunks > max_chunks_per_level {
-                    level += 1;
-                    max_chunks_per_level *= Self::MAX_LINKS;
-                }
-                level
-            }
-        };
+        let (hash, dag_size) = Self::with_bytes_dag(bytes, level, false, options);
+        (options.versioned(hash), bytes.len() as u64 + dag_size)
+    }
+
+    fn level_of(num_bytes: usize) -> u32 {
+        if num_bytes <= Self::CHUNK_SIZE {
+            0
+        } else {
+            let num_chunks = (num_bytes - 1) / Self::CHUNK_SIZE + 1;
 
-        Self::with_bytes_dag(bytes, level, false).0
+            let mut level = 1;
+            let mut max_chunks_per_level = Self::MAX_LINKS;
+
+            while num_chunks > max_chunks_per_level {
+                level += 1;
+                max_chunks_per_level *= Self::MAX_LINKS;
+            }
+            level
+        }
     }
 
-    fn with_bytes_dag(bytes: &[u8], level: u32, is_parallel: bool) -> (Self, u64) {
+    fn with_bytes_dag(
+        bytes: &[u8],
+        level: u32,
+        is_parallel: bool,
+        options: &HashOptions,
+    ) -> (Self, u64) {
         // solve unit chunks
         if level == 0 {
-            return Self::with_bytes_chunk(bytes);
+            return Self::with_bytes_leaf(bytes, options);
         }
 
+        // compose DAG
+        let node = Self::compose_dag(bytes, level, is_parallel, options);
+
+        // compute CID
+        Self::with_bytes_dag_raw(&node)
+    }
+
+    fn compose_dag(
+        bytes: &[u8],
+        level: u32,
+        is_parallel: bool,
+        options: &HashOptions,
+    ) -> ::unixfs::FlatUnixFs<'static> {
         let sublevel = level - 1;
         let chunk_size = Self::CHUNK_SIZE * Self::MAX_LINKS.pow(sublevel);
 
-        // compose DAG
-        let node = ::unixfs::FlatUnixFs {
+        ::unixfs::FlatUnixFs {
             data: ::unixfs::UnixFs {
                 Type: ::unixfs::UnixFsType::File,
                 filesize: Some(bytes.len().try_into().expect("Too large data size")),
@@ -162,7 +294,7 @@ impl Hash {
                 {
                     bytes
                         .chunks(chunk_size)
-                        .map(|chunk| Self::calculate_link(&chunk, sublevel, is_parallel))
+                        .map(|chunk| Self::calculate_link(&chunk, sublevel, is_parallel, options))
                         .collect()
                 }
 
@@ -175,48 +307,84 @@ impl Hash {
                             .to_vec()
                             .into_par_iter()
                             .chunks(chunk_size)
-                            .map(|chunk| Self::calculate_link(&chunk, sublevel, true))
+                            .map(|chunk| Self::calculate_link(&chunk, sublevel, true, options))
                             .collect()
                     } else {
                         bytes
                             .chunks(chunk_size)
-                            .map(|chunk| Self::calculate_link(chunk, sublevel, is_parallel))
+                            .map(|chunk| {
+                                Self::calculate_link(chunk, sublevel, is_parallel, options)
+                            })
                             .collect()
                     }
                 }
             },
-        };
-
-        // compute CID
-        Self::with_bytes_dag_raw(&node)
+        }
     }
 
-    fn calculate_link(chunk: &[u8], sublevel: u32, is_parallel: bool) -> ::unixfs::PBLink<'static> {
-        let (hash, dag_size) = Self::with_bytes_dag(chunk, sublevel, is_parallel);
+    fn calculate_link(
+        chunk: &[u8],
+        sublevel: u32,
+        is_parallel: bool,
+        options: &HashOptions,
+    ) -> ::unixfs::PBLink<'static> {
+        let (hash, dag_size) = Self::with_bytes_dag(chunk, sublevel, is_parallel, options);
 
         ::unixfs::PBLink {
-            Hash: Some(hash.0.to_bytes().into()),
+            Hash: Some(options.versioned(hash).0.to_bytes().into()),
             Name: Some(Default::default()),
             Tsize: Some(chunk.len() as u64 + dag_size),
         }
     }
 
+    fn encode_dag(node: &::unixfs::FlatUnixFs) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut writer = Writer::new(&mut buf);
+        node.write_message(&mut writer)
+            .expect("Failed to write DAG");
+        buf
+    }
+
+    /// Returns the hash with the cumulative size of the DAG, excluding the raw data.
     fn with_bytes_dag_raw(node: &::unixfs::FlatUnixFs) -> (Self, u64) {
         // read hash digest
-        let buf = {
-            let mut buf = Vec::new();
-            let mut writer = Writer::new(&mut buf);
-            node.write_message(&mut writer)
-                .expect("Failed to write DAG");
-            buf
-        };
+        let buf = Self::encode_dag(node);
         let hash = Code::Sha2_256.digest(&buf);
 
+        // sum up the subtrees' sizes
+        let dag_size = buf.len() as u64
+            + node.links.iter().filter_map(|link| link.Tsize).sum::<u64>()
+            - node.data.filesize.unwrap_or_default();
+
         // compose CID
-        (
-            Self(Cid::new_v1(Self::CODEC_DAG_PB, hash)),
-            buf.len() as u64,
-        )
+        (Self(Cid::new_v1(Self::CODEC_DAG_PB, hash)), dag_size)
+    }
+
+    /// Returns the hash of the leaf chunk, encoded as the `options` specify.
+    fn with_bytes_leaf(bytes: &[u8], options: &HashOptions) -> (Self, u64) {
+        match options.leaf_encoding {
+            LeafEncoding::Raw => Self::with_bytes_chunk(bytes),
+            LeafEncoding::DagPb => {
+                // assert chunk size
+                debug_assert!(bytes.len() <= Self::CHUNK_SIZE);
+
+                // wrap the chunk in a UnixFS node
+                let node = ::unixfs::FlatUnixFs {
+                    data: ::unixfs::UnixFs {
+                        Type: ::unixfs::UnixFsType::File,
+                        Data: if bytes.is_empty() {
+                            None
+                        } else {
+                            Some(bytes.into())
+                        },
+                        filesize: Some(bytes.len() as u64),
+                        ..Default::default()
+                    },
+                    links: Default::default(),
+                };
+                Self::with_bytes_dag_raw(&node)
+            }
+        }
     }
 
     fn with_bytes_chunk(bytes: &[u8]) -> (Self, u64) {
@@ -232,9 +400,215 @@ impl Hash {
         (Self(Cid::new_v1(Self::CODEC_RAW, hash)), 0)
     }
 
+    pub fn with_bytes_options(bytes: &[u8], options: &HashOptions) -> Self {
+        match options.try_inline(bytes) {
+            Some(hash) => hash,
+            None => Self::with_bytes_sized(bytes, options).0,
+        }
+    }
+
+    /// Returns the CID of the DAG-CBOR block, as IPLD tools would address it.
+    pub fn with_dag_cbor(bytes: &[u8]) -> Self {
+        Self(Cid::new_v1(
+            Self::CODEC_DAG_CBOR,
+            Code::Sha2_256.digest(bytes),
+        ))
+    }
+
+    /// Wraps the SHA-256 digest computed elsewhere, such as a Merkle root.
+    pub(crate) fn with_sha2_256_digest(digest: &[u8; 32]) -> Self {
+        Self(Cid::new_v1(
+            Self::CODEC_RAW,
+            Multihash::wrap(Code::Sha2_256.into(), digest).expect("SHA-256 digest has 32 bytes"),
+        ))
+    }
+
     pub fn with_str(msg: &str) -> Self {
         Self::with_bytes(msg.as_bytes())
     }
+
+    pub fn with_str_options(msg: &str, options: &HashOptions) -> Self {
+        Self::with_bytes_options(msg.as_bytes(), options)
+    }
+
+    fn with_bytes_inline(bytes: &[u8]) -> Self {
+        // read hash digest
+        let hash = Multihash::wrap(Self::MULTIHASH_IDENTITY, bytes).expect("Too large data size");
+
+        // compose CID
+        Self(Cid::new_v1(Self::CODEC_RAW, hash))
+    }
+
+    pub fn is_inlined(&self) -> bool {
+        self.0.hash().code() == Self::MULTIHASH_IDENTITY
+    }
+
+    /// Returns the payload embedded in the identity hash, if any.
+    pub fn inlined_bytes(&self) -> Option<&[u8]> {
+        if self.is_inlined() {
+            Some(self.0.hash().digest())
+        } else {
+            None
+        }
+    }
+
+    /// Hashes the stream chunk by chunk, so that the memory usage is bounded.
+    pub fn from_reader(mut reader: impl Read) -> Result<Self> {
+        let mut hasher = Hasher::default();
+        let mut buf = vec![0; Self::CHUNK_SIZE];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break Ok(hasher.finalize()),
+                Ok(len) => hasher.update(&buf[..len]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => break Err(e.into()),
+            }
+        }
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        File::open(path)
+            .map_err(Into::into)
+            .and_then(Self::from_reader)
+    }
+
+    /// Hashes the async stream chunk by chunk.
+    ///
+    /// The chunks are hashed on the blocking thread pool, so that the executor is not blocked.
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_reader(
+        mut reader: impl ::tokio::io::AsyncRead + Unpin,
+    ) -> Result<Self> {
+        use tokio::{io::AsyncReadExt, task::spawn_blocking};
+
+        let mut hasher = Hasher::default();
+        let mut buf = vec![0; Self::CHUNK_SIZE];
+        loop {
+            // fill the chunk buffer
+            let mut len = 0;
+            while len < buf.len() {
+                match reader.read(&mut buf[len..]).await? {
+                    0 => break,
+                    read => len += read,
+                }
+            }
+            if len == 0 {
+                break;
+            }
+
+            // hash the chunk
+            (hasher, buf) = spawn_blocking(move || {
+                hasher.update(&buf[..len]);
+                (hasher, buf)
+            })
+            .await?;
+        }
+
+        spawn_blocking(move || hasher.finalize())
+            .await
+            .map_err(Into::into)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn from_async_path(path: impl AsRef<Path>) -> Result<Self> {
+        let file = ::tokio::fs::File::open(path).await?;
+        Self::from_async_reader(file).await
+    }
+
+    pub const fn version(&self) -> Version {
+        self.0.version()
+    }
+
+    /// Converts into the legacy `Qm...` form.
+    ///
+    /// Only DAG-PB nodes hashed with sha2-256 can be represented as CIDv0.
+    pub fn to_v0(&self) -> Result<Self> {
+        if self.0.codec() != Self::CODEC_DAG_PB {
+            bail!("only DAG-PB hashes can be represented as CIDv0");
+        }
+
+        Cid::new_v0(*self.0.hash()).map(Self).map_err(Into::into)
+    }
+
+    pub fn to_v1(&self) -> Self {
+        // note: CIDv0 is always DAG-PB, so the conversion cannot fail
+        Self(self.0.into_v1().expect("CIDv0 should be DAG-PB"))
+    }
+
+    /// Renders the hash in the given multibase.
+    ///
+    /// CIDv0 can only be rendered in base58btc; convert it with [`Self::to_v1`] first.
+    pub fn to_string_of_base(&self, base: Base) -> Result<String> {
+        self.0.to_string_of_base(base).map_err(Into::into)
+    }
+}
+
+/// The encoding of the leaf chunks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LeafEncoding {
+    /// Raw (`0x55`) leaves, as `ipfs add --raw-leaves`
+    #[default]
+    Raw,
+    /// DAG-PB leaves wrapping the chunks in UnixFS nodes, as `ipfs add --raw-leaves=false`
+    DagPb,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HashOptions {
+    inline_threshold: usize,
+    leaf_encoding: LeafEncoding,
+    cid_version: Version,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        Self {
+            inline_threshold: 0,
+            leaf_encoding: Default::default(),
+            cid_version: Version::V1,
+        }
+    }
+}
+
+impl HashOptions {
+    /// should be matched with the multihash's max digest size
+    pub const MAX_INLINE_THRESHOLD: usize = 64;
+
+    /// Embeds the payloads up to `size` bytes into identity hashes, instead of hashing them.
+    ///
+    /// The size is limited to [`Self::MAX_INLINE_THRESHOLD`]; `0` disables inlining.
+    pub fn inline_threshold(mut self, size: usize) -> Self {
+        self.inline_threshold = size.min(Self::MAX_INLINE_THRESHOLD);
+        self
+    }
+
+    pub fn leaf_encoding(mut self, encoding: LeafEncoding) -> Self {
+        self.leaf_encoding = encoding;
+        self
+    }
+
+    /// Sets the CID version of the DAG-PB nodes, including their links.
+    ///
+    /// Raw leaves are always CIDv1, as they cannot be represented as CIDv0.
+    pub fn cid_version(mut self, version: Version) -> Self {
+        self.cid_version = version;
+        self
+    }
+
+    fn versioned(&self, hash: Hash) -> Hash {
+        match self.cid_version {
+            Version::V0 => hash.to_v0().unwrap_or(hash),
+            Version::V1 => hash,
+        }
+    }
+
+    fn try_inline(&self, bytes: &[u8]) -> Option<Hash> {
+        if self.inline_threshold > 0 && bytes.len() <= self.inline_threshold {
+            Some(Hash::with_bytes_inline(bytes))
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -242,9 +616,17 @@ pub struct Hasher {
     buf: Vec<u8>,
     len: usize,
     nodes: Vec<::unixfs::FlatUnixFs<'static>>,
+    options: HashOptions,
 }
 
 impl Hasher {
+    pub fn with_options(options: HashOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
     fn push(&mut self, sublevel: usize, chunk_size: u64, hash: Hash, dag_size: u64) {
         // reserve a slot in the tree
         if sublevel == 0 {
@@ -295,6 +677,7 @@ impl Hasher {
         *node.data.filesize.as_mut().unwrap() += chunk_size;
 
         // update the Links
+        let hash = self.options.versioned(hash);
         node.links.push(::unixfs::PBLink {
             Hash: Some(hash.0.to_bytes().into()),
             Name: Some(Default::default()),
@@ -310,7 +693,12 @@ impl Hasher {
         self.len
     }
 
-    pub fn update(&mut self, mut bytes: &[u8]) {
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.update_with(bytes, |_| ())
+    }
+
+    /// Updates the hasher, inspecting the hashes of the completed leaf chunks in order.
+    fn update_with(&mut self, mut bytes: &[u8], mut on_leaf: impl FnMut(Hash)) {
         self.len += bytes.len();
 
         // add full chunks
@@ -333,7 +721,8 @@ impl Hasher {
             {
                 // read hash digest
                 let chunk_size = chunk.len() as u64;
-                let (hash, dag_size) = Hash::with_bytes_chunk(&chunk);
+                let (hash, dag_size) = Hash::with_bytes_leaf(&chunk, &self.options);
+                on_leaf(hash);
 
                 // get or create the leaf node
                 self.push(0, chunk_size, hash, dag_size);
@@ -344,21 +733,29 @@ impl Hasher {
         self.buf.extend_from_slice(bytes);
     }
 
-    pub fn finalize(mut self) -> Hash {
+    pub fn finalize(self) -> Hash {
+        self.finalize_sized().0
+    }
+
+    /// Returns the hash with the cumulative size of the DAG, including the raw data.
+    fn finalize_sized(mut self) -> (Hash, u64) {
         // if there is no DAG, then return the raw chunk's hash
         if self.nodes.is_empty() {
             // read hash digest
-            let (hash, _) = Hash::with_bytes_chunk(&self.buf);
+            let (hash, dag_size) = match self.options.try_inline(&self.buf) {
+                Some(hash) => (hash, 0),
+                None => Hash::with_bytes_leaf(&self.buf, &self.options),
+            };
 
             // compose CID
-            return hash;
+            return (self.options.versioned(hash), self.len as u64 + dag_size);
         }
 
         // insert the unfulfilled chunk
         if !self.buf.is_empty() {
             // read hash digest
             let chunk_size = self.buf.len() as u64;
-            let (hash, dag_size) = Hash::with_bytes_chunk(&self.buf);
+            let (hash, dag_size) = Hash::with_bytes_leaf(&self.buf, &self.options);
 
             // get or create the leaf node
             self.push(0, chunk_size, hash, dag_size);
@@ -377,9 +774,183 @@ impl Hasher {
         }
 
         // read hash digest
-        let (hash, _) = Hash::with_bytes_dag_raw(self.nodes.last().unwrap());
+        let (hash, dag_size) = Hash::with_bytes_dag_raw(self.nodes.last().unwrap());
 
         // compose CID
-        hash
+        (self.options.versioned(hash), self.len as u64 + dag_size)
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A streaming hasher which hashes the leaf chunks in parallel.
+///
+/// The incoming bytes are collected into batches of chunks; each full batch is handed to the
+/// rayon thread pool while the next one is being filled, and the hashed batches are linked
+/// into the DAG in order, so the result is always equal to [`Hasher`]'s.
+///
+/// Up to [`ParallelHasher::MAX_PENDING_BATCHES`] batches are hashed at once;
+/// `update` blocks on the oldest one beyond that, bounding the memory usage.
+#[cfg(not(target_os = "wasi"))]
+#[derive(Debug)]
+pub struct ParallelHasher {
+    buf: Vec<u8>,
+    batch_size: usize,
+    /// The hashed leaves of the batches in flight, in order
+    pending: VecDeque<Receiver<Vec<HashedLeaf>>>,
+    inner: Hasher,
+}
+
+/// The chunk size and the hash of a leaf, with the cumulative size of its DAG
+#[cfg(not(target_os = "wasi"))]
+type HashedLeaf = (u64, (Hash, u64));
+
+#[cfg(not(target_os = "wasi"))]
+impl Default for ParallelHasher {
+    fn default() -> Self {
+        Self::with_batch_size(::rayon::current_num_threads() * 4)
+    }
+}
+
+#[cfg(not(target_os = "wasi"))]
+impl ParallelHasher {
+    /// Creates a hasher which buffers up to `batch_size` chunks at once.
+    pub fn with_batch_size(batch_size: usize) -> Self {
+        Self {
+            buf: Default::default(),
+            batch_size: batch_size.max(1),
+            pending: Default::default(),
+            inner: Default::default(),
+        }
+    }
+
+    pub fn with_options(options: HashOptions) -> Self {
+        Self {
+            inner: Hasher::with_options(options),
+            ..Default::default()
+        }
+    }
+
+    /// The max number of the batches being hashed at once
+    pub const MAX_PENDING_BATCHES: usize = 2;
+
+    fn hash_batch(batch: &[u8], options: &HashOptions) -> Vec<HashedLeaf> {
+        use rayon::prelude::*;
+
+        // read hash digests
+        batch
+            .par_chunks(Hash::CHUNK_SIZE)
+            .map(|chunk| (chunk.len() as u64, Hash::with_bytes_leaf(chunk, options)))
+            .collect()
+    }
+
+    fn flush_batch(&mut self, len: usize) {
+        // retain the rest for the next batch
+        let mut rest = Vec::with_capacity(self.buf.capacity());
+        rest.extend_from_slice(&self.buf[len..]);
+        self.buf.truncate(len);
+        let batch = ::core::mem::replace(&mut self.buf, rest);
+        let options = self.inner.options;
+
+        // note: a worker waiting for its own pool may deadlock, so hash in place
+        if ::rayon::current_thread_index().is_some() {
+            self.join_pending();
+            let leaves = Self::hash_batch(&batch, &options);
+            self.push_leaves(leaves);
+            return;
+        }
+
+        // wait for the oldest batch if too many are in flight
+        while self.pending.len() >= Self::MAX_PENDING_BATCHES {
+            self.join_oldest();
+        }
+
+        let (tx, rx) = sync_channel(1);
+        ::rayon::spawn(move || {
+            // note: the receiver may be dropped along with the hasher
+            let _ = tx.send(Self::hash_batch(&batch, &options));
+        });
+        self.pending.push_back(rx);
+    }
+
+    fn join_oldest(&mut self) {
+        if let Some(rx) = self.pending.pop_front() {
+            let leaves = rx.recv().expect("Failed to hash the batch");
+            self.push_leaves(leaves);
+        }
+    }
+
+    fn join_pending(&mut self) {
+        while !self.pending.is_empty() {
+            self.join_oldest();
+        }
+    }
+
+    fn push_leaves(&mut self, leaves: Vec<HashedLeaf>) {
+        // insert to the leaf node, keeping the order
+        for (chunk_size, (hash, dag_size)) in leaves {
+            self.inner.push(0, chunk_size, hash, dag_size);
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub const fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.inner.len += bytes.len();
+
+        let batch_len = self.batch_size * Hash::CHUNK_SIZE;
+        while !bytes.is_empty() {
+            // fill the batch buffer
+            let len = bytes.len().min(batch_len + 1 - self.buf.len());
+            self.buf.extend_from_slice(&bytes[..len]);
+            bytes = &bytes[len..];
+
+            // add full chunks, retaining the last one
+            if self.buf.len() > batch_len {
+                self.flush_batch(batch_len);
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> Hash {
+        self.join_pending();
+
+        // if there is no DAG, then return the raw chunk's hash
+        if self.inner.nodes.is_empty() && self.buf.len() <= Hash::CHUNK_SIZE {
+            self.inner.buf = ::core::mem::take(&mut self.buf);
+            return self.inner.finalize();
+        }
+
+        // insert the remaining chunks
+        let leaves = Self::hash_batch(&self.buf, &self.inner.options);
+        self.push_leaves(leaves);
+        self.inner.finalize()
+    }
+}
+
+#[cfg(not(target_os = "wasi"))]
+impl Write for ParallelHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
@@ -0,0 +1,416 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path},
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use super::{Hash, HashOptions, Hasher};
+
+#[derive(Clone, Debug, Default)]
+pub struct DirectoryBuilder {
+    entries: BTreeMap<String, Entry>,
+    options: HashOptions,
+}
+
+#[derive(Clone, Debug)]
+enum Entry {
+    Link { hash: Hash, size: u64 },
+    Directory(DirectoryBuilder),
+}
+
+impl DirectoryBuilder {
+    /// should be matched with IPFS's HAMT sharding threshold
+    const HAMT_SHARDING_SIZE: usize = 262_144;
+
+    /// should be matched with IPFS's HAMT fanout
+    const HAMT_FANOUT: u64 = 256;
+
+    /// Murmur3 (x64, 64 bits) multihash code
+    const HAMT_HASH_TYPE: u64 = 0x22;
+
+    /// Hashes the files and the directory nodes as the options specify, as `ipfs add -r` would.
+    ///
+    /// The inline threshold applies to the files only.
+    pub fn with_options(options: HashOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Adds a file, creating the missing parent directories.
+    pub fn add_bytes(&mut self, path: impl AsRef<Path>, bytes: &[u8]) -> Result<&mut Self> {
+        let (hash, size) = match self.options.try_inline(bytes) {
+            Some(hash) => (hash, bytes.len() as u64),
+            None => Hash::with_bytes_sized(bytes, &self.options),
+        };
+        self.insert(path.as_ref(), Entry::Link { hash, size })
+    }
+
+    /// Adds a file from the stream, creating the missing parent directories.
+    pub fn add_reader(
+        &mut self,
+        path: impl AsRef<Path>,
+        mut reader: impl ::std::io::Read,
+    ) -> Result<&mut Self> {
+        let mut hasher = Hasher::with_options(self.options);
+        ::std::io::copy(&mut reader, &mut hasher)?;
+
+        let (hash, size) = hasher.finalize_sized();
+        self.insert(path.as_ref(), Entry::Link { hash, size })
+    }
+
+    /// Adds a symbolic link, creating the missing parent directories.
+    pub fn add_symlink(&mut self, path: impl AsRef<Path>, target: &str) -> Result<&mut Self> {
+        let node = ::unixfs::FlatUnixFs {
+            data: ::unixfs::UnixFs {
+                Type: ::unixfs::UnixFsType::Symlink,
+                Data: Some(target.as_bytes().to_vec().into()),
+                ..Default::default()
+            },
+            links: Default::default(),
+        };
+        let (hash, size) = Hash::with_bytes_dag_raw(&node);
+
+        let hash = self.options.versioned(hash);
+        self.insert(path.as_ref(), Entry::Link { hash, size })
+    }
+
+    /// Adds an empty directory, creating the missing parent directories.
+    pub fn add_directory(&mut self, path: impl AsRef<Path>) -> Result<&mut Self> {
+        let names = Self::parse_path(path.as_ref())?;
+        if names.is_empty() {
+            bail!("empty path");
+        }
+
+        self.get_or_create_directory(&names)?;
+        Ok(self)
+    }
+
+    fn insert(&mut self, path: &Path, entry: Entry) -> Result<&mut Self> {
+        let mut names = Self::parse_path(path)?;
+        let name = names.pop().ok_or_else(|| anyhow!("empty path"))?;
+
+        let directory = self.get_or_create_directory(&names)?;
+        if directory.entries.contains_key(&name) {
+            bail!("duplicated path: {}", path.display());
+        }
+        directory.entries.insert(name, entry);
+        Ok(self)
+    }
+
+    fn get_or_create_directory(&mut self, names: &[String]) -> Result<&mut Self> {
+        match names.split_first() {
+            Some((name, names)) => {
+                let options = self.options;
+                match self
+                    .entries
+                    .entry(name.clone())
+                    .or_insert_with(|| Entry::Directory(Self::with_options(options)))
+                {
+                    Entry::Directory(directory) => directory.get_or_create_directory(names),
+                    Entry::Link { .. } => bail!("not a directory: {name}"),
+                }
+            }
+            None => Ok(self),
+        }
+    }
+
+    fn parse_path(path: &Path) -> Result<Vec<String>> {
+        path.components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .map(|component| match component {
+                Component::Normal(name) => name
+                    .to_str()
+                    .map(ToString::to_string)
+                    .ok_or_else(|| anyhow!("non UTF-8 path: {}", path.display())),
+                _ => bail!("unsupported path: {}", path.display()),
+            })
+            .collect()
+    }
+
+    pub fn build(&self) -> Result<Hash> {
+        self.build_sized().map(|(hash, _)| hash)
+    }
+
+    fn build_sized(&self) -> Result<(Hash, u64)> {
+        // collect the children
+        let links: Vec<_> = self
+            .entries
+            .iter()
+            .map(|(name, entry)| {
+                let (hash, size) = match entry {
+                    Entry::Link { hash, size } => (*hash, *size),
+                    Entry::Directory(directory) => directory.build_sized()?,
+                };
+                Ok((name.as_str(), hash, size))
+            })
+            .collect::<Result<_>>()?;
+
+        // estimate the size of the node
+        let estimated_size: usize = links
+            .iter()
+            .map(|(name, hash, _)| name.len() + hash.0.to_bytes().len())
+            .sum();
+
+        if estimated_size >= Self::HAMT_SHARDING_SIZE {
+            let mut shard = HamtShard::default();
+            for (name, hash, size) in links {
+                let value = HamtValue {
+                    name,
+                    digest: murmur3_x64_64(name.as_bytes()).to_be_bytes(),
+                    hash,
+                    size,
+                };
+                shard.insert(value, 0)?;
+            }
+            Ok(shard.build(&self.options))
+        } else {
+            let node = ::unixfs::FlatUnixFs {
+                data: ::unixfs::UnixFs {
+                    Type: ::unixfs::UnixFsType::Directory,
+                    ..Default::default()
+                },
+                links: links
+                    .into_iter()
+                    .map(|(name, hash, size)| Hash::to_link(name.to_string(), hash, size))
+                    .collect(),
+            };
+            let (hash, size) = Hash::with_bytes_dag_raw(&node);
+            Ok((self.options.versioned(hash), size))
+        }
+    }
+}
+
+#[derive(Default)]
+struct HamtShard<'a> {
+    children: BTreeMap<u8, HamtChild<'a>>,
+}
+
+enum HamtChild<'a> {
+    Value(HamtValue<'a>),
+    Shard(HamtShard<'a>),
+}
+
+struct HamtValue<'a> {
+    name: &'a str,
+    digest: [u8; 8],
+    hash: Hash,
+    size: u64,
+}
+
+impl<'a> HamtShard<'a> {
+    fn insert(&mut self, value: HamtValue<'a>, depth: usize) -> Result<()> {
+        // note: the fanout 256 consumes a byte per level
+        let index = *value
+            .digest
+            .get(depth)
+            .ok_or_else(|| anyhow!("HAMT hash collision: {}", value.name))?;
+
+        match self.children.remove(&index) {
+            None => {
+                self.children.insert(index, HamtChild::Value(value));
+            }
+            Some(HamtChild::Value(sibling)) => {
+                // replace the value with another shard, one level deeper
+                let mut shard = Self::default();
+                shard.insert(sibling, depth + 1)?;
+                shard.insert(value, depth + 1)?;
+                self.children.insert(index, HamtChild::Shard(shard));
+            }
+            Some(HamtChild::Shard(mut shard)) => {
+                shard.insert(value, depth + 1)?;
+                self.children.insert(index, HamtChild::Shard(shard));
+            }
+        }
+        Ok(())
+    }
+
+    fn build(&self, options: &HashOptions) -> (Hash, u64) {
+        // compose the bitfield, stripping the leading zeros
+        let mut bitfield = [0u8; DirectoryBuilder::HAMT_FANOUT as usize / 8];
+        for &index in self.children.keys() {
+            bitfield[bitfield.len() - 1 - index as usize / 8] |= 1 << (index % 8);
+        }
+        let bitfield = match bitfield.iter().position(|&byte| byte != 0) {
+            Some(offset) => bitfield[offset..].to_vec(),
+            None => Vec::new(),
+        };
+
+        let node = ::unixfs::FlatUnixFs {
+            data: ::unixfs::UnixFs {
+                Type: ::unixfs::UnixFsType::HAMTShard,
+                Data: Some(bitfield.into()),
+                hashType: Some(DirectoryBuilder::HAMT_HASH_TYPE),
+                fanout: Some(DirectoryBuilder::HAMT_FANOUT),
+                ..Default::default()
+            },
+            links: self
+                .children
+                .iter()
+                .map(|(index, child)| match child {
+                    HamtChild::Value(value) => {
+                        Hash::to_link(format!("{index:02X}{}", value.name), value.hash, value.size)
+                    }
+                    HamtChild::Shard(shard) => {
+                        let (hash, size) = shard.build(options);
+                        Hash::to_link(format!("{index:02X}"), hash, size)
+                    }
+                })
+                .collect(),
+        };
+        let (hash, size) = Hash::with_bytes_dag_raw(&node);
+        (options.versioned(hash), size)
+    }
+}
+
+/// The first half of Murmur3 x64 128-bit hash, seeded with 0.
+fn murmur3_x64_64(bytes: &[u8]) -> u64 {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    const fn fmix64(mut k: u64) -> u64 {
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        k ^= k >> 33;
+        k
+    }
+
+    let mut h1 = 0u64;
+    let mut h2 = 0u64;
+
+    // body
+    let mut blocks = bytes.chunks_exact(16);
+    for block in &mut blocks {
+        let k1 = u64::from_le_bytes(block[..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..].try_into().unwrap());
+
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+        h1 = h1
+            .rotate_left(27)
+            .wrapping_add(h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+        h2 = h2
+            .rotate_left(31)
+            .wrapping_add(h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+
+    // tail
+    let tail = blocks.remainder();
+    if tail.len() > 8 {
+        let k2 = tail[8..]
+            .iter()
+            .rev()
+            .fold(0u64, |k, &byte| (k << 8) | byte as u64);
+        h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+    }
+    if !tail.is_empty() {
+        let k1 = tail[..tail.len().min(8)]
+            .iter()
+            .rev()
+            .fold(0u64, |k, &byte| (k << 8) | byte as u64);
+        h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    }
+
+    // finalization
+    h1 ^= bytes.len() as u64;
+    h2 ^= bytes.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1.wrapping_add(h2)
+}
+
+impl Hash {
+    pub fn directory_builder() -> DirectoryBuilder {
+        DirectoryBuilder::default()
+    }
+
+    /// Hashes the directory recursively, as `ipfs add -r` does.
+    ///
+    /// Hidden files are skipped and symbolic links are not followed.
+    pub fn from_directory(path: impl AsRef<Path>) -> Result<Self> {
+        fn walk(builder: &mut DirectoryBuilder, path: &Path, prefix: &Path) -> Result<()> {
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+                let name = entry.file_name();
+                if name.to_string_lossy().starts_with('.') {
+                    continue;
+                }
+
+                let path = entry.path();
+                let prefix = prefix.join(&name);
+                let file_type = entry.file_type()?;
+                if file_type.is_dir() {
+                    builder.add_directory(&prefix)?;
+                    walk(builder, &path, &prefix)?;
+                } else if file_type.is_symlink() {
+                    let target = fs::read_link(&path)?;
+                    let target = target
+                        .to_str()
+                        .ok_or_else(|| anyhow!("non UTF-8 path: {}", target.display()))?;
+                    builder.add_symlink(&prefix, target)?;
+                } else {
+                    builder.add_reader(&prefix, fs::File::open(&path)?)?;
+                }
+            }
+            Ok(())
+        }
+
+        let mut builder = Self::directory_builder();
+        walk(&mut builder, path.as_ref(), Path::new(""))?;
+        builder.build()
+    }
+
+    fn to_link(name: String, hash: Self, size: u64) -> ::unixfs::PBLink<'static> {
+        ::unixfs::PBLink {
+            Hash: Some(hash.0.to_bytes().into()),
+            Name: Some(name.into()),
+            Tsize: Some(size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_murmur3() {
+        assert_eq!(murmur3_x64_64(b""), 0);
+        assert_eq!(murmur3_x64_64(b"hello"), 0xcbd8_a7b3_41bd_9b02);
+    }
+
+    #[test]
+    fn test_hamt_collision() {
+        let value = |name| HamtValue {
+            name,
+            digest: murmur3_x64_64(b"hello").to_be_bytes(),
+            hash: Hash::with_str(name),
+            size: 0,
+        };
+
+        // the full 64-bit collision cannot be sharded further
+        let mut shard = HamtShard::default();
+        assert!(shard.insert(value("foo"), 0).is_ok());
+        assert!(shard.insert(value("bar"), 0).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use core::ops::Range;
+
+use anyhow::{anyhow, bail, Result};
+use bytecheck::CheckBytes;
+use cid::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::Hash;
+
+/// An inclusion proof of a byte range, composed of the DAG-PB nodes from the root to the leaves.
+///
+/// The leaf chunks are not included; they should be given to [`RangeProof::verify`] separately.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct RangeProof {
+    pub filesize: u64,
+    pub range: Range<u64>,
+    /// The encoded DAG-PB nodes, in depth-first order
+    pub nodes: Vec<Vec<u8>>,
+}
+
+impl RangeProof {
+    /// Returns the range of the leaf chunks covering the proven range.
+    pub fn leaves_range(&self) -> Range<u64> {
+        let chunk_size = Hash::CHUNK_SIZE as u64;
+
+        let start = self.range.start / chunk_size * chunk_size;
+        let end = (self.range.end.div_ceil(chunk_size) * chunk_size).min(self.filesize);
+        start..end
+    }
+
+    /// Verifies the leaf chunks against the root hash, returning the bytes of the proven range.
+    ///
+    /// The `leaves` should be the bytes in [`RangeProof::leaves_range`].
+    pub fn verify<'a>(&self, root: &Hash, leaves: &'a [u8]) -> Result<&'a [u8]> {
+        let leaves_range = self.leaves_range();
+        if self.range.start >= self.range.end || self.range.end > self.filesize {
+            bail!("invalid range: {:?}", &self.range);
+        }
+        if leaves.len() as u64 != leaves_range.end - leaves_range.start {
+            bail!("leaves size mismatching");
+        }
+
+        let mut nodes = self.nodes.iter();
+        let mut verifier = RangeVerifier {
+            leaves,
+            leaves_range: leaves_range.clone(),
+            nodes: &mut nodes,
+        };
+        verifier.verify(root, 0, self.filesize)?;
+
+        if nodes.next().is_some() {
+            bail!("too many DAG nodes");
+        }
+
+        let start = self
+            .range
+            .start
+            .checked_sub(leaves_range.start)
+            .ok_or_else(|| anyhow!("leaves out of range"))? as usize;
+        let end = self
+            .range
+            .end
+            .checked_sub(leaves_range.start)
+            .ok_or_else(|| anyhow!("leaves out of range"))? as usize;
+        Ok(&leaves[start..end])
+    }
+}
+
+struct RangeVerifier<'a, 'b, I> {
+    leaves: &'a [u8],
+    leaves_range: Range<u64>,
+    nodes: &'b mut I,
+}
+
+impl<'a, 'b, 'c, I> RangeVerifier<'a, 'b, I>
+where
+    I: Iterator<Item = &'c Vec<u8>>,
+{
+    fn verify(&mut self, hash: &Hash, offset: u64, size: u64) -> Result<()> {
+        match hash.0.codec() {
+            // verify the leaf chunk
+            Hash::CODEC_RAW => {
+                if size > Hash::CHUNK_SIZE as u64 {
+                    bail!("oversized leaf at offset {offset}: {size}");
+                }
+
+                let start = offset
+                    .checked_sub(self.leaves_range.start)
+                    .ok_or_else(|| anyhow!("leaves out of range"))?
+                    as usize;
+                let chunk = self
+                    .leaves
+                    .get(start..start.saturating_add(size as usize))
+                    .ok_or_else(|| anyhow!("leaves out of range"))?;
+
+                if Hash::with_bytes_chunk(chunk).0 != *hash {
+                    bail!("leaf hash mismatch at offset {offset}");
+                }
+                Ok(())
+            }
+            // verify the DAG node
+            Hash::CODEC_DAG_PB => {
+                let buf = self
+                    .nodes
+                    .next()
+                    .ok_or_else(|| anyhow!("missing DAG node at offset {offset}"))?;
+                if Code::Sha2_256.digest(buf) != *hash.0.hash() {
+                    bail!("DAG node hash mismatch at offset {offset}");
+                }
+
+                let node = ::unixfs::FlatUnixFs::try_from(buf.as_slice())
+                    .map_err(|_| anyhow!("malformed DAG node at offset {offset}"))?;
+                if node.data.filesize != Some(size)
+                    || node.data.blocksizes.iter().sum::<u64>() != size
+                    || node.data.blocksizes.len() != node.links.len()
+                {
+                    bail!("DAG node size mismatch at offset {offset}");
+                }
+
+                // visit the children covering the leaves
+                let mut offset = offset;
+                for (link, &size) in node.links.iter().zip(&node.data.blocksizes) {
+                    if offset < self.leaves_range.end && self.leaves_range.start < offset + size {
+                        let hash = link
+                            .Hash
+                            .as_deref()
+                            .ok_or_else(|| anyhow!("missing link hash"))
+                            .and_then(|bytes| Cid::try_from(bytes).map_err(Into::into))
+                            .map(Hash)?;
+                        self.verify(&hash, offset, size)?;
+                    }
+                    offset += size;
+                }
+                Ok(())
+            }
+            codec => bail!("unsupported codec: {codec:#x}"),
+        }
+    }
+}
+
+impl Hash {
+    /// Proves that the `range` of the `bytes` belongs to [`Hash::with_bytes`].
+    pub fn prove_range(bytes: &[u8], range: Range<usize>) -> Result<RangeProof> {
+        if range.start >= range.end || range.end > bytes.len() {
+            bail!("invalid range: {:?}", &range);
+        }
+
+        let mut proof = RangeProof {
+            filesize: bytes.len() as u64,
+            range: range.start as u64..range.end as u64,
+            nodes: Default::default(),
+        };
+        let leaves_range = proof.leaves_range();
+        let leaves_range = leaves_range.start as usize..leaves_range.end as usize;
+
+        fn prove(
+            nodes: &mut Vec<Vec<u8>>,
+            bytes: &[u8],
+            offset: usize,
+            level: u32,
+            leaves_range: &Range<usize>,
+        ) {
+            // skip unit chunks
+            if level == 0 {
+                return;
+            }
+
+            // compose DAG
+            let node = Hash::compose_dag(bytes, level, false, &Default::default());
+            nodes.push(Hash::encode_dag(&node));
+
+            // visit the children covering the leaves
+            let chunk_size = Hash::CHUNK_SIZE * Hash::MAX_LINKS.pow(level - 1);
+            for (index, chunk) in bytes.chunks(chunk_size).enumerate() {
+                let offset = offset + index * chunk_size;
+                if offset < leaves_range.end && leaves_range.start < offset + chunk.len() {
+                    prove(nodes, chunk, offset, level - 1, leaves_range);
+                }
+            }
+        }
+
+        let level = Self::level_of(bytes.len());
+        prove(&mut proof.nodes, bytes, 0, level, &leaves_range);
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unaligned_leaves() {
+        // craft a DAG node whose leaf starts before the chunk boundary
+        let chunk_size = Hash::CHUNK_SIZE as u64;
+        let sizes = [100, chunk_size];
+        let node = ::unixfs::FlatUnixFs {
+            data: ::unixfs::UnixFs {
+                Type: ::unixfs::UnixFsType::File,
+                filesize: Some(sizes.iter().sum()),
+                blocksizes: sizes.to_vec(),
+                ..Default::default()
+            },
+            links: sizes
+                .iter()
+                .map(|&size| {
+                    let (hash, _) = Hash::with_bytes_chunk(&vec![0; size as usize]);
+                    ::unixfs::PBLink {
+                        Hash: Some(hash.0.to_bytes().into()),
+                        Name: Some(Default::default()),
+                        Tsize: Some(size),
+                    }
+                })
+                .collect(),
+        };
+        let buf = Hash::encode_dag(&node);
+        let root = Hash(Cid::new_v1(Hash::CODEC_DAG_PB, Code::Sha2_256.digest(&buf)));
+
+        let proof = RangeProof {
+            filesize: chunk_size + 100,
+            range: chunk_size..chunk_size + 100,
+            nodes: vec![buf],
+        };
+        assert!(proof.verify(&root, &[0; 100]).is_err());
+    }
+
+    #[test]
+    fn test_oversized_leaf() {
+        // claim a raw root covering more than a chunk
+        let chunk_size = Hash::CHUNK_SIZE as u64;
+        let (root, _) = Hash::with_bytes_chunk(&[0; 10]);
+
+        let proof = RangeProof {
+            filesize: chunk_size + 1,
+            range: 0..chunk_size + 1,
+            nodes: Default::default(),
+        };
+        assert!(proof
+            .verify(&root, &vec![0; chunk_size as usize + 1])
+            .is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::io::{self, Read};
+
+use super::{Hash, Hasher};
+
+/// A reader which verifies the content against the expected hash while reading.
+///
+/// The content is always checked at EOF. If the expected leaf hashes are given, each leaf
+/// chunk is checked as soon as it is completed, so that the corruption can be detected early.
+/// Note that the leaf hashes are only hints; the integrity is guaranteed by the root hash.
+#[derive(Debug)]
+pub struct VerifyingReader<R> {
+    reader: R,
+    expected: Hash,
+    hasher: Option<Hasher>,
+    leaves: Option<::std::vec::IntoIter<Hash>>,
+    num_leaves: usize,
+    error: Option<String>,
+}
+
+impl<R> VerifyingReader<R> {
+    pub fn new(reader: R, expected: Hash) -> Self {
+        Self {
+            reader,
+            expected,
+            hasher: Some(Default::default()),
+            leaves: None,
+            num_leaves: 0,
+            error: None,
+        }
+    }
+
+    /// Creates a reader which checks each leaf chunk against the expected leaf hashes in order.
+    pub fn with_leaves(reader: R, expected: Hash, leaves: Vec<Hash>) -> Self {
+        Self {
+            leaves: Some(leaves.into_iter()),
+            ..Self::new(reader, expected)
+        }
+    }
+
+    pub fn expected(&self) -> &Hash {
+        &self.expected
+    }
+
+    /// Returns `true` if all the content has been read and verified.
+    pub fn is_verified(&self) -> bool {
+        self.hasher.is_none() && self.error.is_none()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn fail(&mut self, msg: String) -> io::Error {
+        self.hasher = None;
+        self.error = Some(msg.clone());
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+}
+
+impl<R> Read for VerifyingReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(msg) = &self.error {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg.clone()));
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let len = self.reader.read(buf)?;
+        let hasher = match self.hasher.as_mut() {
+            Some(hasher) => hasher,
+            // the content is already verified
+            None => return Ok(len),
+        };
+
+        // verify the whole content at EOF
+        if len == 0 {
+            let hash = self.hasher.take().unwrap().finalize();
+            if hash != self.expected {
+                return Err(self.fail(format!(
+                    "hash mismatch: expected {}, but given {}",
+                    self.expected.to_string(),
+                    hash.to_string(),
+                )));
+            }
+            return Ok(0);
+        }
+
+        // verify the completed leaf chunks
+        let mut mismatch = None;
+        hasher.update_with(&buf[..len], |hash| {
+            if mismatch.is_some() {
+                return;
+            }
+            if let Some(leaves) = self.leaves.as_mut() {
+                match leaves.next() {
+                    Some(expected) if expected == hash => {}
+                    Some(expected) => {
+                        mismatch = Some(format!(
+                            "leaf hash mismatch at chunk #{}: expected {}, but given {}",
+                            self.num_leaves,
+                            expected.to_string(),
+                            hash.to_string(),
+                        ))
+                    }
+                    None => mismatch = Some("too many leaf chunks".to_string()),
+                }
+            }
+            self.num_leaves += 1;
+        });
+        match mismatch {
+            Some(msg) => Err(self.fail(msg)),
+            None => Ok(len),
+        }
+    }
+}
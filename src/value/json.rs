@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use base58::FromBase58;
+use ndarray::{ArcArray, ArrayView, IxDyn};
+use serde_json::{Map, Number, Value as Json};
+
+use super::{array::Array, bytes::Bytes, text::Text, Value, ValueType};
+
+impl Value {
+    /// The key of the type in the annotated JSON
+    pub const JSON_KEY_TYPE: &'static str = "type";
+
+    /// The key of the value in the annotated JSON
+    pub const JSON_KEY_VALUE: &'static str = "value";
+
+    /// The key of the array shape in the annotated JSON
+    pub const JSON_KEY_SHAPE: &'static str = "shape";
+
+    /// Converts into the natural JSON, which may lose the exact type.
+    ///
+    /// Numbers become JSON numbers (non-finite floats become `null`), the string-like
+    /// types become their [`ToString`] forms, and the arrays become nested JSON arrays.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Self::None => Json::Null,
+            Self::Dyn => Json::Object(Default::default()),
+            Self::Bool(value) => Json::Bool(*value),
+            Self::I8(value) => (*value).into(),
+            Self::I16(value) => (*value).into(),
+            Self::I32(value) => (*value).into(),
+            Self::I64(value) => (*value).into(),
+            Self::U8(value) => (*value).into(),
+            Self::U16(value) => (*value).into(),
+            Self::U32(value) => (*value).into(),
+            Self::U64(value) => (*value).into(),
+            Self::F32(value) => (*value).into(),
+            Self::F64(value) => (*value).into(),
+            Self::Text(value) => Json::String(value.msg.clone()),
+            Self::UnitInterval(value) => value.to_num::<f64>().into(),
+            Self::Bytes(_) | Self::Hash(_) | Self::DateTime(_) | Self::Uuid(_) => {
+                Json::String(self.to_string())
+            }
+            Self::List(values) => Json::Array(values.iter().map(Self::to_json).collect()),
+            Self::Map(values) => Json::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+            Self::ArrayU8(value) => nested_to_json(value.view(), &|&value| value.into()),
+            Self::ArrayI64(value) => nested_to_json(value.view(), &|&value| value.into()),
+            Self::ArrayF32(value) => nested_to_json(value.view(), &|&value| value.into()),
+            Self::ArrayF64(value) => nested_to_json(value.view(), &|&value| value.into()),
+        }
+    }
+
+    /// Converts from the natural JSON, inferring the types.
+    ///
+    /// Integers become `I64`, or `U64` if too large; the other numbers become `F64`.
+    /// Strings become `Text` in `en-us`, arrays become `List`, and objects become `Map`.
+    pub fn from_json(json: &Json) -> Result<Self> {
+        Ok(match json {
+            Json::Null => Self::None,
+            Json::Bool(value) => Self::Bool(*value),
+            Json::Number(value) => {
+                if let Some(value) = value.as_i64() {
+                    Self::I64(value)
+                } else if let Some(value) = value.as_u64() {
+                    Self::U64(value)
+                } else {
+                    Self::F64(
+                        value
+                            .as_f64()
+                            .ok_or_else(|| anyhow!("unsupported JSON number: {value}"))?,
+                    )
+                }
+            }
+            Json::String(value) => Self::Text(Text::with_en_us(value)),
+            Json::Array(values) => {
+                Self::List(values.iter().map(Self::from_json).collect::<Result<_>>()?)
+            }
+            Json::Object(values) => Self::Map(
+                values
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), Self::from_json(value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    /// Converts into the annotated JSON, which preserves the exact type.
+    ///
+    /// Every value becomes an object of `{"type": ValueType, "value": ...}`; the arrays also
+    /// come with their `shape` and the flattened `value`. Non-finite floats are written as
+    /// the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    pub fn to_json_annotated(&self) -> Json {
+        let value = match self {
+            Self::None | Self::Dyn => None,
+            Self::F32(value) => Some(float_to_json((*value).into())),
+            Self::F64(value) => Some(float_to_json(*value)),
+            Self::Text(value) => Some(Json::Object(Map::from_iter([
+                ("msg".to_string(), Json::String(value.msg.clone())),
+                ("lang".to_string(), Json::String(value.lang.to_string())),
+            ]))),
+            Self::List(values) => Some(Json::Array(
+                values.iter().map(Self::to_json_annotated).collect(),
+            )),
+            Self::Map(values) => Some(Json::Object(
+                values
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json_annotated()))
+                    .collect(),
+            )),
+            Self::ArrayU8(value) => Some(flat_to_json(value, |&value| value.into())),
+            Self::ArrayI64(value) => Some(flat_to_json(value, |&value| value.into())),
+            Self::ArrayF32(value) => {
+                Some(flat_to_json(value, |&value| float_to_json(value.into())))
+            }
+            Self::ArrayF64(value) => Some(flat_to_json(value, |&value| float_to_json(value))),
+            _ => Some(self.to_json()),
+        };
+
+        let mut json = Map::new();
+        json.insert(
+            Self::JSON_KEY_TYPE.to_string(),
+            ::serde_json::to_value(self.value_type()).expect("ValueType should be serialized"),
+        );
+        if let Some(shape) = self.shape() {
+            json.insert(
+                Self::JSON_KEY_SHAPE.to_string(),
+                shape.iter().copied().collect(),
+            );
+        }
+        if let Some(value) = value {
+            json.insert(Self::JSON_KEY_VALUE.to_string(), value);
+        }
+        Json::Object(json)
+    }
+
+    /// Converts from the annotated JSON, restoring the exact type.
+    pub fn from_json_annotated(json: &Json) -> Result<Self> {
+        let json = json
+            .as_object()
+            .ok_or_else(|| anyhow!("expected an annotated JSON object, but given {json}"))?;
+        let ty: ValueType = json
+            .get(Self::JSON_KEY_TYPE)
+            .cloned()
+            .ok_or_else(|| anyhow!("missing the value type"))
+            .and_then(|ty| ::serde_json::from_value(ty).map_err(Into::into))?;
+        let value = || {
+            json.get(Self::JSON_KEY_VALUE)
+                .ok_or_else(|| anyhow!("missing the value of {ty:?}"))
+        };
+        let shape = || -> Result<Vec<usize>> {
+            json.get(Self::JSON_KEY_SHAPE)
+                .and_then(Json::as_array)
+                .ok_or_else(|| anyhow!("missing the shape of {ty:?}"))?
+                .iter()
+                .map(|len| {
+                    len.as_u64()
+                        .and_then(|len| len.try_into().ok())
+                        .ok_or_else(|| anyhow!("malformed shape: {len}"))
+                })
+                .collect()
+        };
+        let string = || {
+            value()?
+                .as_str()
+                .ok_or_else(|| anyhow!("expected a string of {ty:?}"))
+        };
+
+        match ty {
+            ValueType::None => Ok(Self::None),
+            ValueType::Dyn => Ok(Self::Dyn),
+            ValueType::Bool => value()?
+                .as_bool()
+                .map(Self::Bool)
+                .ok_or_else(|| anyhow!("expected a boolean")),
+            ValueType::I8
+            | ValueType::I16
+            | ValueType::I32
+            | ValueType::I64
+            | ValueType::U8
+            | ValueType::U16
+            | ValueType::U32
+            | ValueType::U64 => {
+                let value = value()?;
+                match (value.as_i64(), value.as_u64()) {
+                    (Some(value), _) => Self::I64(value).cast(ty),
+                    (None, Some(value)) => Self::U64(value).cast(ty),
+                    (None, None) => bail!("expected an integer of {ty:?}, but given {value}"),
+                }
+            }
+            ValueType::F32 => float_from_json(value()?).map(|value| Self::F32(value as f32)),
+            ValueType::F64 => float_from_json(value()?).map(Self::F64),
+            ValueType::Bytes => string()?
+                .from_base58()
+                .map(|bytes| Self::Bytes(Bytes(bytes)))
+                .map_err(|_| anyhow!("malformed base58 bytes")),
+            ValueType::Text => {
+                let field = |key| {
+                    value()?
+                        .get(key)
+                        .and_then(Json::as_str)
+                        .ok_or_else(|| anyhow!("missing the {key} of Text"))
+                };
+                Ok(Self::Text(Text {
+                    msg: field("msg")?.to_string(),
+                    lang: field("lang")?.parse()?,
+                }))
+            }
+            ValueType::Hash | ValueType::DateTime | ValueType::Uuid => Self::parse(string()?, ty),
+            ValueType::UnitInterval => {
+                let value = value()?
+                    .as_f64()
+                    .ok_or_else(|| anyhow!("expected a number of {ty:?}"))?;
+                Self::F64(value).cast(ty)
+            }
+            ValueType::List => value()?
+                .as_array()
+                .ok_or_else(|| anyhow!("expected an array of {ty:?}"))?
+                .iter()
+                .map(Self::from_json_annotated)
+                .collect::<Result<_>>()
+                .map(Self::List),
+            ValueType::Map => value()?
+                .as_object()
+                .ok_or_else(|| anyhow!("expected an object of {ty:?}"))?
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), Self::from_json_annotated(value)?)))
+                .collect::<Result<BTreeMap<_, _>>>()
+                .map(Self::Map),
+            ValueType::ArrayU8 => flat_from_json(shape()?, value()?, |value| {
+                value.as_u64().and_then(|value| value.try_into().ok())
+            })
+            .map(Self::ArrayU8),
+            ValueType::ArrayI64 => {
+                flat_from_json(shape()?, value()?, Json::as_i64).map(Self::ArrayI64)
+            }
+            ValueType::ArrayF32 => flat_from_json(shape()?, value()?, |value| {
+                float_from_json(value).ok().map(|value| value as f32)
+            })
+            .map(Self::ArrayF32),
+            ValueType::ArrayF64 => {
+                flat_from_json(shape()?, value()?, |value| float_from_json(value).ok())
+                    .map(Self::ArrayF64)
+            }
+        }
+    }
+
+    pub(crate) fn shape(&self) -> Option<&[usize]> {
+        match self {
+            Self::ArrayU8(value) => Some(value.shape()),
+            Self::ArrayI64(value) => Some(value.shape()),
+            Self::ArrayF32(value) => Some(value.shape()),
+            Self::ArrayF64(value) => Some(value.shape()),
+            _ => None,
+        }
+    }
+}
+
+fn nested_to_json<A>(array: ArrayView<A, IxDyn>, f: &dyn Fn(&A) -> Json) -> Json {
+    if array.ndim() == 0 {
+        array.first().map(f).unwrap_or_default()
+    } else {
+        Json::Array(
+            array
+                .outer_iter()
+                .map(|array| nested_to_json(array, f))
+                .collect(),
+        )
+    }
+}
+
+fn flat_to_json<A>(array: &Array<A, IxDyn>, f: impl Fn(&A) -> Json) -> Json {
+    // note: the elements are visited in the logical (row-major) order
+    Json::Array(array.iter().map(f).collect())
+}
+
+fn flat_from_json<A>(
+    shape: Vec<usize>,
+    json: &Json,
+    f: impl Fn(&Json) -> Option<A>,
+) -> Result<Array<A, IxDyn>> {
+    let data = json
+        .as_array()
+        .ok_or_else(|| anyhow!("expected an array of the elements"))?
+        .iter()
+        .map(|value| f(value).ok_or_else(|| anyhow!("malformed array element: {value}")))
+        .collect::<Result<Vec<_>>>()?;
+
+    ArcArray::from_shape_vec(IxDyn(&shape), data)
+        .map(Array)
+        .map_err(Into::into)
+}
+
+fn float_to_json(value: f64) -> Json {
+    match Number::from_f64(value) {
+        Some(value) => Json::Number(value),
+        None if value.is_nan() => Json::String("NaN".to_string()),
+        None if value.is_sign_positive() => Json::String("Infinity".to_string()),
+        None => Json::String("-Infinity".to_string()),
+    }
+}
+
+fn float_from_json(json: &Json) -> Result<f64> {
+    match json {
+        Json::Number(value) => value
+            .as_f64()
+            .ok_or_else(|| anyhow!("malformed float: {value}")),
+        Json::String(value) => match value.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => bail!("malformed float: {value:?}"),
+        },
+        _ => bail!("expected a float, but given {json}"),
+    }
+}
+
+impl From<&Value> for Json {
+    fn from(value: &Value) -> Self {
+        value.to_json()
+    }
+}
+
+impl TryFrom<&Json> for Value {
+    type Error = ::anyhow::Error;
+
+    fn try_from(json: &Json) -> Result<Self, Self::Error> {
+        Self::from_json(json)
+    }
+}
@@ -1,14 +1,20 @@
 pub mod array;
 pub mod bytes;
+mod cast;
 pub mod chrono;
 pub mod hash;
+mod json;
 pub mod nonce;
 pub mod primitives;
+pub mod schema;
 pub mod text;
 pub mod unit_interval;
 pub mod uuid;
 
+use std::collections::BTreeMap;
+
 use bytecheck::CheckBytes;
+use ndarray::IxDyn;
 use rkyv::{Archive, Deserialize, Serialize};
 
 #[derive(
@@ -21,8 +27,17 @@ use rkyv::{Archive, Deserialize, Serialize};
     ::serde::Serialize,
     ::serde::Deserialize,
 )]
-#[archive(compare(PartialEq))]
-#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+#[archive(
+    bound(serialize = "__S: ::rkyv::ser::ScratchSpace + ::rkyv::ser::Serializer"),
+    compare(PartialEq)
+)]
+#[archive_attr(
+    derive(CheckBytes, Debug, PartialEq),
+    check_bytes(bound = "
+        __C: ::rkyv::validation::ArchiveContext,
+        <__C as ::rkyv::Fallible>::Error: ::std::error::Error,
+    ")
+)]
 pub enum Value {
     None,
     Dyn,
@@ -39,6 +54,24 @@ pub enum Value {
     F64(f64),
     Bytes(self::bytes::Bytes),
     Text(self::text::Text),
+    Hash(self::hash::Hash),
+    DateTime(self::chrono::DateTime),
+    Uuid(self::uuid::Uuid),
+    UnitInterval(self::unit_interval::UnitInterval),
+    List(
+        #[omit_bounds]
+        #[archive_attr(omit_bounds)]
+        Vec<Value>,
+    ),
+    Map(
+        #[omit_bounds]
+        #[archive_attr(omit_bounds)]
+        BTreeMap<String, Value>,
+    ),
+    ArrayU8(self::array::Array<u8, IxDyn>),
+    ArrayI64(self::array::Array<i64, IxDyn>),
+    ArrayF32(self::array::Array<f32, IxDyn>),
+    ArrayF64(self::array::Array<f64, IxDyn>),
 }
 
 impl ToString for Value {
@@ -59,6 +92,30 @@ impl ToString for Value {
             Self::F64(value) => value.to_string(),
             Self::Bytes(value) => value.to_string(),
             Self::Text(value) => value.to_string(),
+            Self::Hash(value) => value.to_string(),
+            Self::DateTime(value) => value.to_rfc3339(),
+            Self::Uuid(value) => value.to_string(),
+            Self::UnitInterval(value) => value.to_string(),
+            Self::List(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Self::Map(values) => format!(
+                "{{{}}}",
+                values
+                    .iter()
+                    .map(|(key, value)| format!("{key:?}: {}", value.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            Self::ArrayU8(value) => value.to_string(),
+            Self::ArrayI64(value) => value.to_string(),
+            Self::ArrayF32(value) => value.to_string(),
+            Self::ArrayF64(value) => value.to_string(),
         }
     }
 }
@@ -96,4 +153,14 @@ pub enum ValueType {
     F64,
     Bytes,
     Text,
+    Hash,
+    DateTime,
+    Uuid,
+    UnitInterval,
+    List,
+    Map,
+    ArrayU8,
+    ArrayI64,
+    ArrayF32,
+    ArrayF64,
 }
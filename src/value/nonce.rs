@@ -54,4 +54,14 @@ impl Nonce {
     pub fn generate() -> Self {
         Self(Uuid::generate())
     }
+
+    /// Generates a random (v4) nonce from the given source, such as a seeded one.
+    pub fn with_rng<R>(rng: &mut R) -> Self
+    where
+        R: ::rand::RngCore + ?Sized,
+    {
+        let mut bytes = [0; 16];
+        rng.fill_bytes(&mut bytes);
+        Self(Uuid(::uuid::Builder::from_random_bytes(bytes).into_uuid()))
+    }
 }
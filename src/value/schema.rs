@@ -0,0 +1,283 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use anyhow::{bail, Result};
+use bytecheck::CheckBytes;
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::{hash::Hash, text::LanguageTag, Value, ValueType};
+
+/// A schema describing the shape of [`Value`] documents.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(
+    bound(serialize = "__S: ::rkyv::ser::ScratchSpace + ::rkyv::ser::Serializer"),
+    compare(PartialEq)
+)]
+#[archive_attr(
+    derive(CheckBytes, Debug, PartialEq),
+    check_bytes(bound = "
+        __C: ::rkyv::validation::ArchiveContext,
+        <__C as ::rkyv::Fallible>::Error: ::std::error::Error,
+    ")
+)]
+pub enum Schema {
+    /// Any value of the type; `Dyn` accepts every value
+    Type(ValueType),
+    /// Numbers of the type, within the inclusive bounds
+    Range {
+        ty: ValueType,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    /// Texts in one of the language ranges; empty accepts every language
+    Text { langs: Vec<LanguageTag> },
+    /// One of the values
+    Enum(Vec<Value>),
+    /// Lists of which every item conforms to the schema
+    List(
+        #[omit_bounds]
+        #[archive_attr(omit_bounds)]
+        Box<Schema>,
+    ),
+    /// Maps with the fields, rejecting the unknown fields unless `additional` is set
+    Object {
+        #[omit_bounds]
+        #[archive_attr(omit_bounds)]
+        fields: BTreeMap<String, Field>,
+        additional: bool,
+    },
+}
+
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    Archive,
+    Serialize,
+    Deserialize,
+    ::serde::Serialize,
+    ::serde::Deserialize,
+)]
+#[archive(
+    bound(serialize = "__S: ::rkyv::ser::ScratchSpace + ::rkyv::ser::Serializer"),
+    compare(PartialEq)
+)]
+#[archive_attr(
+    derive(CheckBytes, Debug, PartialEq),
+    check_bytes(bound = "
+        __C: ::rkyv::validation::ArchiveContext,
+        <__C as ::rkyv::Fallible>::Error: ::std::error::Error,
+    ")
+)]
+pub struct Field {
+    #[omit_bounds]
+    #[archive_attr(omit_bounds)]
+    pub schema: Schema,
+    pub required: bool,
+}
+
+impl Field {
+    pub fn required(schema: Schema) -> Self {
+        Self {
+            schema,
+            required: true,
+        }
+    }
+
+    pub fn optional(schema: Schema) -> Self {
+        Self {
+            schema,
+            required: false,
+        }
+    }
+}
+
+/// A violation of the schema, located by the path from the root, e.g. `$.tags[0]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub path: String,
+    pub message: String,
+}
+
+impl ::core::fmt::Display for Violation {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        write!(f, "{}: {}", &self.path, &self.message)
+    }
+}
+
+impl Schema {
+    /// Creates an object schema which rejects the unknown fields.
+    pub fn object(fields: impl IntoIterator<Item = (String, Field)>) -> Self {
+        Self::Object {
+            fields: fields.into_iter().collect(),
+            additional: false,
+        }
+    }
+
+    /// Returns the content address of the schema, so that the payloads can refer to it.
+    pub fn to_hash(&self) -> Result<Hash> {
+        ::rkyv::to_bytes::<_, 64>(self)
+            .map(|bytes| Hash::with_bytes(&bytes))
+            .map_err(Into::into)
+    }
+
+    /// Validates the value, failing with all the violations.
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        let violations = self.violations(value);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            bail!(
+                "schema violations: {}",
+                violations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+        }
+    }
+
+    /// Returns every violation of the value, in depth-first order.
+    pub fn violations(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.visit(value, &mut "$".to_string(), &mut violations);
+        violations
+    }
+
+    fn visit(&self, value: &Value, path: &mut String, violations: &mut Vec<Violation>) {
+        let mut report = |message: String| {
+            violations.push(Violation {
+                path: path.clone(),
+                message,
+            })
+        };
+
+        match self {
+            Self::Type(ValueType::Dyn) => {}
+            Self::Type(ty) => {
+                if value.value_type() != *ty {
+                    report(format!(
+                        "expected {ty:?}, but given {:?}",
+                        value.value_type()
+                    ));
+                }
+            }
+            Self::Range { ty, min, max } => {
+                if value.value_type() != *ty {
+                    report(format!(
+                        "expected {ty:?}, but given {:?}",
+                        value.value_type()
+                    ));
+                    return;
+                }
+
+                // note: the integers are compared as is, as f64 loses the precision above 2^53
+                let (out_of_range, number) = match (value.to_integer(), value.to_float()) {
+                    (Some(integer), _) => (
+                        min.and_then(|min| compare_integer(integer, min)) == Some(Ordering::Less)
+                            || max.and_then(|max| compare_integer(integer, max))
+                                == Some(Ordering::Greater),
+                        integer.to_string(),
+                    ),
+                    (None, Some(float)) => (
+                        min.map(|min| float < min).unwrap_or_default()
+                            || max.map(|max| float > max).unwrap_or_default()
+                            || float.is_nan(),
+                        float.to_string(),
+                    ),
+                    (None, None) => {
+                        report(format!("expected a number, but given {ty:?}"));
+                        return;
+                    }
+                };
+                if out_of_range {
+                    report(format!(
+                        "out of range [{}, {}]: {number}",
+                        min.map(|min| min.to_string()).unwrap_or_default(),
+                        max.map(|max| max.to_string()).unwrap_or_default(),
+                    ));
+                }
+            }
+            Self::Text { langs } => match value {
+                Value::Text(text) => {
+                    if !langs.is_empty() && !langs.iter().any(|lang| lang.matches(&text.lang)) {
+                        report(format!("unexpected language: {}", text.lang.as_str()));
+                    }
+                }
+                _ => report(format!("expected Text, but given {:?}", value.value_type())),
+            },
+            Self::Enum(values) => {
+                if !values.contains(value) {
+                    report(format!("unexpected value: {}", value.to_string()));
+                }
+            }
+            Self::List(schema) => match value {
+                Value::List(items) => {
+                    for (index, item) in items.iter().enumerate() {
+                        let len = path.len();
+                        path.push_str(&format!("[{index}]"));
+                        schema.visit(item, path, violations);
+                        path.truncate(len);
+                    }
+                }
+                _ => report(format!("expected List, but given {:?}", value.value_type())),
+            },
+            Self::Object { fields, additional } => match value {
+                Value::Map(map) => {
+                    for (key, field) in fields {
+                        let len = path.len();
+                        path.push_str(&format!(".{key}"));
+                        match map.get(key) {
+                            Some(value) => field.schema.visit(value, path, violations),
+                            None if field.required => violations.push(Violation {
+                                path: path.clone(),
+                                message: "missing required field".to_string(),
+                            }),
+                            None => {}
+                        }
+                        path.truncate(len);
+                    }
+
+                    if !additional {
+                        for key in map.keys().filter(|key| !fields.contains_key(*key)) {
+                            violations.push(Violation {
+                                path: format!("{path}.{key}"),
+                                message: "unexpected field".to_string(),
+                            });
+                        }
+                    }
+                }
+                _ => report(format!("expected Map, but given {:?}", value.value_type())),
+            },
+        }
+    }
+}
+
+/// Compares the integer with the float exactly, or returns `None` if the float is NaN.
+fn compare_integer(integer: i128, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+
+    // note: every integral f64 within the range of i128 is exactly representable
+    let floor = float.floor();
+    if floor >= i128::MAX as f64 {
+        Some(Ordering::Less)
+    } else if floor < i128::MIN as f64 {
+        Some(Ordering::Greater)
+    } else {
+        match integer.cmp(&(floor as i128)) {
+            Ordering::Equal if floor < float => Some(Ordering::Less),
+            ordering => Some(ordering),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use bytecheck::CheckBytes;
+use ipi::{
+    account::Account, batch::MerkleTree, metadata::Metadata, signed::IsSigned, value::hash::Hash,
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct MyData {
+    pub msg: String,
+    pub num: u64,
+}
+
+impl IsSigned for MyData {}
+
+fn data(len: u64) -> Vec<MyData> {
+    (0..len)
+        .map(|num| MyData {
+            msg: "Hello world!".to_string(),
+            num,
+        })
+        .collect()
+}
+
+#[test]
+fn batch_proofs() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    for len in 1..=17 {
+        let data = data(len);
+        let (root, proofs) = Metadata::builder()
+            .build_batch(&guarantee, guarantor.account_ref(), &data)
+            .unwrap();
+        assert_eq!(proofs.len(), data.len());
+
+        // every record is included
+        for (record, proof) in data.iter().zip(&proofs) {
+            proof
+                .verify(record, &root, Some(&guarantor.account_ref()))
+                .unwrap();
+            assert!(proof.siblings.len() <= 64 - (len - 1).leading_zeros() as usize);
+        }
+
+        // but the others are not
+        let other = MyData {
+            msg: "Goodbye world!".to_string(),
+            num: 0,
+        };
+        assert!(proofs[0].verify(&other, &root, None).is_err());
+        if len > 1 {
+            assert!(proofs[1].verify(&data[0], &root, None).is_err());
+        }
+    }
+}
+
+#[test]
+fn batch_tampered() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    let data = data(5);
+    let (root, proofs) = Metadata::builder()
+        .build_batch(&guarantee, guarantor.account_ref(), &data)
+        .unwrap();
+
+    // the root is the one of the tree
+    let hashes: Vec<_> = data
+        .iter()
+        .map(|record| Hash::with_bytes(&::rkyv::to_bytes::<_, 64>(record).unwrap()))
+        .collect();
+    assert_eq!(root.hash, MerkleTree::new(&hashes).unwrap().root());
+    assert!(MerkleTree::new(&[]).is_err());
+
+    // detect the tampered proofs
+    let mut proof = proofs[2].clone();
+    proof.siblings[0][0] ^= 1;
+    assert!(proof.verify(&data[2], &root, None).is_err());
+
+    let mut proof = proofs[2].clone();
+    proof.siblings.pop();
+    assert!(proof.verify(&data[2], &root, None).is_err());
+
+    let mut proof = proofs[2].clone();
+    proof.siblings.push([0; 32]);
+    assert!(proof.verify(&data[2], &root, None).is_err());
+
+    let mut proof = proofs[4].clone();
+    proof.index = 5;
+    assert!(proof.verify(&data[4], &root, None).is_err());
+
+    let mut proof = proofs[4].clone();
+    proof.len = 6;
+    assert!(proof.verify(&data[4], &root, None).is_err());
+
+    // detect the other guarantors
+    assert!(proofs[2]
+        .verify(&data[2], &root, Some(&guarantee.account_ref()))
+        .is_err());
+
+    // detect the tampered root
+    let mut forged = root;
+    forged.data.hash = Hash::with_bytes(b"forged");
+    assert!(proofs[2].verify(&data[2], &forged, None).is_err());
+
+    let (other, _) = Metadata::builder()
+        .build_batch(&guarantee, guarantor.account_ref(), &data[..4])
+        .unwrap();
+    assert!(proofs[2].verify(&data[2], &other, None).is_err());
+}
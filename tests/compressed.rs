@@ -0,0 +1,111 @@
+use bytecheck::CheckBytes;
+use ipi::{
+    account::{Account, GuarantorSigned, Verifier},
+    compressed::{Compressed, Compression},
+    data::Data,
+    signed::{IsSigned, SERIALIZER_HEAP_SIZE},
+    store::{MemoryStore, Store},
+};
+use rkyv::{de::deserializers::SharedDeserializeMap, Archive, Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct MyData {
+    pub msg: String,
+    pub values: Vec<u64>,
+}
+
+impl IsSigned for MyData {}
+
+fn data() -> MyData {
+    MyData {
+        msg: "Hello world!".to_string(),
+        values: (0..65_536).map(|value| value % 7).collect(),
+    }
+}
+
+#[test]
+fn compressed_payload() {
+    let data = data();
+
+    for compression in [Compression::Lz4, Compression::Zstd] {
+        let compressed = Compressed::compress(&data, compression).unwrap();
+        assert!(compressed.bytes.len() < compressed.len as usize / 4);
+        assert_eq!(compressed.decompress::<MyData>().unwrap(), data);
+
+        // detect the tampered length
+        let mut tampered = compressed.clone();
+        tampered.len -= 1;
+        assert!(tampered.decompress_archived().is_err());
+        tampered.len += 2;
+        assert!(tampered.decompress_archived().is_err());
+        tampered.len = u64::MAX;
+        assert!(tampered.decompress_archived().is_err());
+
+        // detect the tampered compression
+        let mut tampered = compressed;
+        tampered.compression = match compression {
+            Compression::Lz4 => Compression::Zstd,
+            Compression::Zstd => Compression::Lz4,
+        };
+        assert!(tampered.decompress_archived().is_err());
+    }
+}
+
+#[test]
+fn compressed_data() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    let data = data();
+    let plain = Data::builder()
+        .build(&guarantee, guarantor.account_ref(), &data)
+        .unwrap();
+
+    for compression in [Compression::Lz4, Compression::Zstd] {
+        // compress and sign as guarantee
+        let signed = Data::builder()
+            .build_compressed(&guarantee, guarantor.account_ref(), &data, compression)
+            .unwrap();
+
+        // the compression does not change the identity
+        assert_eq!(signed.metadata.hash, plain.metadata.hash);
+        assert_eq!(signed.data.to_hash().unwrap(), plain.metadata.hash);
+        assert_eq!(
+            signed.data.to_payload_hash().unwrap(),
+            Some(plain.metadata.hash),
+        );
+
+        // sign as guarantor
+        let signed = signed.sign(&guarantor).unwrap();
+        signed.verify(Some(&guarantor.account_ref())).unwrap();
+        MemoryStore::new().put(&signed).unwrap();
+
+        // archive
+        let bytes = ::rkyv::to_bytes::<_, SERIALIZER_HEAP_SIZE>(&signed).unwrap();
+        let archived =
+            ::rkyv::check_archived_root::<Data<GuarantorSigned, Compressed>>(&bytes[..]).unwrap();
+        let signed: Data<GuarantorSigned, Compressed> =
+            Deserialize::deserialize(archived, &mut SharedDeserializeMap::default()).unwrap();
+
+        // decompress on access
+        let decompressed = signed.decompress::<MyData>().unwrap();
+        assert_eq!(decompressed.data, data);
+        decompressed.verify(Some(&guarantor.account_ref())).unwrap();
+
+        // detect the compressed data of the other payloads
+        let mut swapped = signed;
+        swapped.data = Compressed::compress(
+            &MyData {
+                msg: "Goodbye world!".to_string(),
+                values: data.values.clone(),
+            },
+            compression,
+        )
+        .unwrap();
+        assert!(swapped.decompress::<MyData>().is_err());
+        assert!(MemoryStore::new().put(&swapped).is_err());
+    }
+}
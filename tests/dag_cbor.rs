@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use ipi::{
+    account::{Account, GuarantorSigned, Verifier},
+    data::Data,
+    ipld::{FromIpld, Ipld, ToIpld},
+    value::{hash::Hash, text::Text, Value},
+};
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn dag_cbor_canonical() {
+    // the empty map is a well-known block
+    let bytes = Ipld::Map(Default::default()).to_dag_cbor().unwrap();
+    assert_eq!(bytes, [0xa0]);
+    assert_eq!(
+        Hash::with_dag_cbor(&bytes).to_string(),
+        "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua",
+    );
+
+    // the keys are sorted by their length first
+    let ipld = Ipld::Map(BTreeMap::from([
+        (
+            "bb".to_string(),
+            Ipld::List(vec![Ipld::Bool(true), Ipld::Null]),
+        ),
+        ("c".to_string(), Ipld::Integer(-1)),
+        ("a".to_string(), Ipld::Integer(1)),
+    ]));
+    let bytes = ipld.to_dag_cbor().unwrap();
+    assert_eq!(bytes, hex("a361610161632062626282f5f6"));
+    assert_eq!(Ipld::from_dag_cbor(&bytes).unwrap(), ipld);
+
+    // the floats are always 64-bit
+    assert_eq!(
+        Ipld::Float(1.5).to_dag_cbor().unwrap(),
+        hex("fb3ff8000000000000"),
+    );
+
+    // the links are the tag 42 with the multibase prefix
+    let hash = Hash::with_dag_cbor(&[0xa0]);
+    let bytes = Ipld::Link(hash).to_dag_cbor().unwrap();
+    assert_eq!(bytes[..9], hex("d82a58250001711220"));
+    assert_eq!(Ipld::from_dag_cbor(&bytes).unwrap(), Ipld::Link(hash));
+
+    // the integers take the shortest form
+    for (value, expected) in [
+        (0, "00"),
+        (23, "17"),
+        (24, "1818"),
+        (256, "190100"),
+        (-1, "20"),
+        (-25, "3818"),
+        (u64::MAX as i128, "1bffffffffffffffff"),
+        (-1 - u64::MAX as i128, "3bffffffffffffffff"),
+    ] {
+        let bytes = Ipld::Integer(value).to_dag_cbor().unwrap();
+        assert_eq!(bytes, hex(expected), "{value}");
+        assert_eq!(Ipld::from_dag_cbor(&bytes).unwrap(), Ipld::Integer(value));
+    }
+    assert!(Ipld::Integer(u64::MAX as i128 + 1).to_dag_cbor().is_err());
+    assert!(Ipld::Float(f64::NAN).to_dag_cbor().is_err());
+
+    // reject the non-canonical forms
+    for bytes in [
+        "1801",               // non-minimal integer
+        "a2616201616101",     // unsorted keys
+        "a2616101616101",     // duplicated keys
+        "fa3fc00000",         // 32-bit float
+        "fb7ff8000000000000", // NaN
+        "9fff",               // indefinite length
+        "f7",                 // undefined
+        "c074",               // the other tags
+        "d82a4100",           // empty CID
+        "0000",               // trailing bytes
+        "5a00000010",         // too long bytes
+        "a1016161",           // non-string keys
+    ] {
+        assert!(Ipld::from_dag_cbor(&hex(bytes)).is_err(), "{bytes}");
+    }
+
+    // reject too deep documents
+    let bytes = [vec![0x81; 1_000], vec![0xf6]].concat();
+    assert!(Ipld::from_dag_cbor(&bytes).is_err());
+}
+
+#[test]
+fn dag_cbor_value() {
+    let value = Value::Map(BTreeMap::from([
+        ("count".to_string(), Value::U8(5)),
+        ("ratio".to_string(), Value::F32(f32::INFINITY)),
+        ("name".to_string(), Value::Text(Text::with_en_us("foo"))),
+        ("link".to_string(), Value::Hash(Hash::with_str("foo"))),
+        (
+            "tags".to_string(),
+            Value::List(vec![Value::Bool(true), Value::None]),
+        ),
+    ]));
+
+    let bytes = value.to_dag_cbor().unwrap();
+    assert_eq!(Value::from_dag_cbor(&bytes).unwrap(), value);
+
+    // the exact types are preserved
+    let ipld = Ipld::from_dag_cbor(&Value::U8(5).to_dag_cbor().unwrap()).unwrap();
+    assert_eq!(
+        ipld,
+        Ipld::Map(BTreeMap::from([
+            ("type".to_string(), Ipld::String("U8".to_string())),
+            ("value".to_string(), Ipld::Integer(5)),
+        ])),
+    );
+
+    // reject the unknown fields, which would not be signed
+    let mut ipld = match ipld {
+        Ipld::Map(ipld) => ipld,
+        _ => unreachable!(),
+    };
+    ipld.insert("shape".to_string(), Ipld::List(vec![]));
+    assert!(Value::from_ipld(&Ipld::Map(ipld)).is_err());
+}
+
+#[test]
+fn dag_cbor_signing() {
+    let data = Value::Text(Text::with_en_us("Hello world!"));
+
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    // sign over the DAG-CBOR bytes
+    let signed = Data::builder()
+        .build_owned_dag_cbor(&guarantee, guarantor.account_ref(), data.clone())
+        .unwrap()
+        .sign_dag_cbor(&guarantor)
+        .unwrap();
+    assert_eq!(
+        signed.metadata.hash,
+        Hash::with_dag_cbor(&data.to_dag_cbor().unwrap()),
+    );
+
+    // verify
+    signed
+        .verify_dag_cbor(Some(&guarantor.account_ref()))
+        .unwrap();
+    assert!(signed.verify(Some(&guarantor.account_ref())).is_err());
+    assert!(signed
+        .verify_dag_cbor(Some(&guarantee.account_ref()))
+        .is_err());
+
+    // round-trip the envelope
+    let bytes = signed.to_dag_cbor().unwrap();
+    let decoded = Data::<GuarantorSigned, Value>::from_dag_cbor(&bytes).unwrap();
+    assert_eq!(decoded, signed);
+    decoded
+        .verify_dag_cbor(Some(&guarantor.account_ref()))
+        .unwrap();
+
+    // detect the tampered metadata
+    let mut tampered = signed;
+    tampered.metadata.data.data.expiration_date = Some(tampered.metadata.created_date);
+    assert!(tampered.verify_dag_cbor(None).is_err());
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use ipi::{
+    account::{Account, GuarantorSigned, Verifier},
+    data::Data,
+    ipld::{FromIpld, Ipld, ToIpld},
+    value::{hash::Hash, text::Text, Value},
+};
+
+#[test]
+fn dag_json_canonical() {
+    let hash = Hash::with_dag_cbor(&[0xa0]);
+    let ipld = Ipld::Map(BTreeMap::from([
+        ("link".to_string(), Ipld::Link(hash)),
+        ("bytes".to_string(), Ipld::Bytes(b"hello".to_vec())),
+        (
+            "b".to_string(),
+            Ipld::List(vec![Ipld::Integer(-1), Ipld::Float(1.0)]),
+        ),
+        ("a".to_string(), Ipld::Null),
+    ]));
+
+    // the keys are sorted bytewise, without any whitespace
+    let json = ipld.to_dag_json().unwrap();
+    assert_eq!(
+        json,
+        r#"{"a":null,"b":[-1,1.0],"bytes":{"/":{"bytes":"aGVsbG8"}},"link":{"/":"bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua"}}"#,
+    );
+    assert_eq!(Ipld::from_dag_json(&json).unwrap(), ipld);
+
+    // reject the ambiguous maps
+    let ipld = Ipld::Map(BTreeMap::from([("/".to_string(), Ipld::Null)]));
+    assert!(ipld.to_dag_json().is_err());
+    for json in [
+        r#"{"/":null}"#,
+        r#"{"/":"malformed"}"#,
+        r#"{"/":{"bytes":"aGVsbG8="}}"#,
+        r#"{"/":{"bytes":"aGVsbG8","extra":1}}"#,
+    ] {
+        assert!(Ipld::from_dag_json(json).is_err(), "{json}");
+    }
+}
+
+#[test]
+fn dag_json_signed_data() {
+    let data = Value::Map(BTreeMap::from([
+        (
+            "msg".to_string(),
+            Value::Text(Text::with_en_us("Hello world!")),
+        ),
+        ("num".to_string(), Value::U64(42)),
+    ]));
+
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    for is_dag_cbor in [false, true] {
+        let builder = Data::builder();
+        let signed = if is_dag_cbor {
+            builder
+                .build_owned_dag_cbor(&guarantee, guarantor.account_ref(), data.clone())
+                .and_then(|signed| signed.sign_dag_cbor(&guarantor))
+        } else {
+            builder
+                .build_owned(&guarantee, guarantor.account_ref(), data.clone())
+                .and_then(|signed| signed.sign(&guarantor))
+        }
+        .unwrap();
+
+        // export
+        let json = signed.to_dag_json().unwrap();
+        let signature = signed.metadata.guarantor.signature.to_bytes();
+        assert!(json.contains(&format!(
+            r#""signature":{{"/":{{"bytes":"{}"}}}}"#,
+            ::ipi::cid::multibase::Base::Base64.encode(signature),
+        )));
+        assert!(json.contains(&format!(
+            r#""hash":{{"/":"{}"}}"#,
+            signed.metadata.hash.to_string(),
+        )));
+
+        // import
+        let imported = Data::<GuarantorSigned, Value>::from_dag_json(&json).unwrap();
+        assert_eq!(imported, signed);
+        if is_dag_cbor {
+            imported.verify_dag_cbor(Some(&guarantor.account_ref()))
+        } else {
+            imported.verify(Some(&guarantor.account_ref()))
+        }
+        .unwrap();
+
+        // the output is stable
+        assert_eq!(imported.to_dag_json().unwrap(), json);
+    }
+}
@@ -1,4 +1,11 @@
-use ipi::value::hash::{Hash, Hasher};
+use std::io::{ErrorKind, Read, Write};
+
+use ipi::{
+    cid::{multibase::Base, Version},
+    value::hash::{
+        DirectoryBuilder, Hash, HashOptions, Hasher, LeafEncoding, ParallelHasher, VerifyingReader,
+    },
+};
 
 fn test_hash(data: &[u8], expected_cid: &str) {
     // hash with Hash::with_bytes
@@ -26,6 +33,63 @@ fn test_hash(data: &[u8], expected_cid: &str) {
     }
     let hash = hasher.finalize().to_string();
     assert_eq!(hash, expected_cid);
+
+    // hash with Hasher, as a writer
+    let mut hasher = Hasher::default();
+    hasher.write_all(data).unwrap();
+    let hash = hasher.finalize().to_string();
+    assert_eq!(hash, expected_cid);
+
+    // hash with ParallelHasher
+    let mut hasher = ParallelHasher::default();
+    hasher.update(data);
+    let hash = hasher.finalize().to_string();
+    assert_eq!(hash, expected_cid);
+
+    // hash with ParallelHasher, coming with small chunks and small batches
+    let mut hasher = ParallelHasher::with_batch_size(1);
+    for chunk in data.chunks(1_000) {
+        hasher.update(chunk);
+    }
+    let hash = hasher.finalize().to_string();
+    assert_eq!(hash, expected_cid);
+
+    // hash with ParallelHasher, coming with large chunks
+    let mut hasher = ParallelHasher::with_batch_size(3);
+    for chunk in data.chunks(262_144 * 2 + 1) {
+        hasher.update(chunk);
+    }
+    let hash = hasher.finalize().to_string();
+    assert_eq!(hash, expected_cid);
+
+    // hash with ParallelHasher, inside the thread pool
+    let pool = ::rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    let hash = pool.install(|| {
+        let mut hasher = ParallelHasher::with_batch_size(1);
+        for chunk in data.chunks(262_144) {
+            hasher.update(chunk);
+        }
+        hasher.finalize().to_string()
+    });
+    assert_eq!(hash, expected_cid);
+
+    // hash with Hash::from_reader
+    let hash = Hash::from_reader(data).unwrap().to_string();
+    assert_eq!(hash, expected_cid);
+
+    // hash with Hash::from_async_reader
+    #[cfg(feature = "tokio")]
+    {
+        let runtime = ::tokio::runtime::Runtime::new().unwrap();
+        let hash = runtime
+            .block_on(Hash::from_async_reader(data))
+            .unwrap()
+            .to_string();
+        assert_eq!(hash, expected_cid);
+    }
 }
 
 #[test]
@@ -59,3 +123,429 @@ fn hash_dag_level_2_entry() {
 
     test_hash(data, expected_cid);
 }
+
+#[test]
+fn hash_path() {
+    let data = &[0; 262_144 * 174 + 1];
+    let expected_cid = "bafybeihqwzd3o6q6v3pmwhzjy22vokhr767burokmqemg63hptx2nqd7ym";
+
+    // store the data
+    let path = ::std::env::temp_dir().join(format!("ipi-hash-path-{}", ::std::process::id()));
+    ::std::fs::write(&path, data).unwrap();
+
+    // hash with Hash::from_path
+    let hash = Hash::from_path(&path).map(|hash| hash.to_string());
+    ::std::fs::remove_file(&path).unwrap();
+    assert_eq!(hash.unwrap(), expected_cid);
+}
+
+#[test]
+fn hash_cid_v0() {
+    let data = &[0; 262_144 * 174];
+    let expected_cid_v0 = "QmS5Yg93T6cG7VXdqvvCWKQQ9axmLi2kFiVHY6QBn2Hw39";
+    let expected_cid_v1 = "bafybeibxsa3ioclowpaq7b6gxl65gzqneopfr3fnhedak6sqr4bjz5lnyq";
+
+    // convert to CIDv0
+    let hash = Hash::with_bytes(data);
+    let hash_v0 = hash.to_v0().unwrap();
+    assert_eq!(hash_v0.version(), Version::V0);
+    assert_eq!(hash_v0.to_string(), expected_cid_v0);
+
+    // convert back to CIDv1
+    assert_eq!(hash_v0.to_v1(), hash);
+    assert_eq!(hash_v0.to_v1().to_string(), expected_cid_v1);
+
+    // parse CIDv0
+    let parsed: Hash = expected_cid_v0.parse().unwrap();
+    assert_eq!(parsed, hash_v0);
+
+    // raw leaves cannot be represented as CIDv0
+    assert!(Hash::with_bytes(b"hello world").to_v0().is_err());
+}
+
+#[test]
+fn hash_multibase() {
+    let data = &[0; 262_144 * 174];
+    let hash = Hash::with_bytes(data);
+
+    for (base, expected_cid) in [
+        (
+            Base::Base58Btc,
+            "zdj7WZAo3a7Ja5rzCqZafspNhExmoAD4FSt82tpCtnrbTrqD5",
+        ),
+        (
+            Base::Base32Lower,
+            "bafybeibxsa3ioclowpaq7b6gxl65gzqneopfr3fnhedak6sqr4bjz5lnyq",
+        ),
+        (
+            Base::Base36Lower,
+            "k2jmtxsqyr4byvg47zt4bi5j8swwabkogg34u03qmeqsvsjqfu6lgibo",
+        ),
+        (
+            Base::Base64Url,
+            "uAXASIDeQNocJbrPBD4fGuv3TZg0jnljsrTkGBXpQjwKc9W3E",
+        ),
+    ] {
+        // render
+        assert_eq!(hash.to_string_of_base(base).unwrap(), expected_cid);
+
+        // parse
+        let parsed: Hash = expected_cid.parse().unwrap();
+        assert_eq!(parsed, hash);
+    }
+
+    // CIDv0 can only be rendered in base58btc
+    let hash_v0 = hash.to_v0().unwrap();
+    assert!(hash_v0.to_string_of_base(Base::Base58Btc).is_ok());
+    assert!(hash_v0.to_string_of_base(Base::Base32Lower).is_err());
+}
+
+#[test]
+fn hash_directory_empty() {
+    let expected_cid = "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354";
+
+    let hash = Hash::directory_builder().build().unwrap().to_string();
+    assert_eq!(hash, expected_cid);
+
+    // ipfs add -r --cid-version=0
+    let options = HashOptions::default().cid_version(Version::V0);
+    let hash = DirectoryBuilder::with_options(options)
+        .build()
+        .unwrap()
+        .to_string();
+    assert_eq!(hash, "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn");
+}
+
+#[test]
+fn hash_directory_options() {
+    let options = HashOptions::default()
+        .leaf_encoding(LeafEncoding::DagPb)
+        .cid_version(Version::V0);
+    let data = vec![42; 262_144 * 2 + 1];
+
+    // hash the files as the options specify
+    let mut builder = DirectoryBuilder::with_options(options);
+    builder
+        .add_bytes("nested/dir/large.bin", &data)
+        .unwrap()
+        .add_symlink("link", "nested/dir/large.bin")
+        .unwrap();
+    let hash = builder.build().unwrap();
+    assert_eq!(hash.version(), Version::V0);
+
+    let mut builder = DirectoryBuilder::with_options(options);
+    builder
+        .add_reader("nested/dir/large.bin", &data[..])
+        .unwrap()
+        .add_symlink("link", "nested/dir/large.bin")
+        .unwrap();
+    assert_eq!(builder.build().unwrap(), hash);
+
+    let mut builder = Hash::directory_builder();
+    builder
+        .add_bytes("nested/dir/large.bin", &data)
+        .unwrap()
+        .add_symlink("link", "nested/dir/large.bin")
+        .unwrap();
+    assert_ne!(builder.build().unwrap().to_v1(), hash.to_v1());
+}
+
+#[cfg(unix)]
+#[test]
+fn hash_directory_symlink() {
+    // store the data
+    let path = ::std::env::temp_dir().join(format!("ipi-hash-symlink-{}", ::std::process::id()));
+    ::std::fs::create_dir_all(&path).unwrap();
+    ::std::fs::write(path.join("small.txt"), b"hello world").unwrap();
+    ::std::os::unix::fs::symlink("small.txt", path.join("link")).unwrap();
+
+    // the symbolic links should not be followed
+    let hash = Hash::from_directory(&path);
+    ::std::fs::remove_dir_all(&path).unwrap();
+    let hash = hash.unwrap();
+
+    let mut builder = Hash::directory_builder();
+    builder
+        .add_bytes("small.txt", b"hello world")
+        .unwrap()
+        .add_symlink("link", "small.txt")
+        .unwrap();
+    assert_eq!(hash, builder.build().unwrap());
+
+    let mut builder = Hash::directory_builder();
+    builder
+        .add_bytes("small.txt", b"hello world")
+        .unwrap()
+        .add_bytes("link", b"hello world")
+        .unwrap();
+    assert_ne!(hash, builder.build().unwrap());
+}
+
+#[test]
+fn hash_directory() {
+    // store the data
+    let path = ::std::env::temp_dir().join(format!("ipi-hash-directory-{}", ::std::process::id()));
+    ::std::fs::create_dir_all(path.join("empty")).unwrap();
+    ::std::fs::create_dir_all(path.join("nested/dir")).unwrap();
+    ::std::fs::write(path.join("small.txt"), b"hello world").unwrap();
+    ::std::fs::write(path.join("nested/dir/large.bin"), [0; 262_144 * 2]).unwrap();
+    ::std::fs::write(path.join(".hidden"), b"hidden").unwrap();
+
+    // hash with Hash::from_directory
+    let hash = Hash::from_directory(&path);
+    ::std::fs::remove_dir_all(&path).unwrap();
+
+    // hash with DirectoryBuilder
+    let mut builder = Hash::directory_builder();
+    builder
+        .add_directory("empty")
+        .unwrap()
+        .add_bytes("small.txt", b"hello world")
+        .unwrap()
+        .add_reader("nested/dir/large.bin", &[0; 262_144 * 2][..])
+        .unwrap();
+    // FIXME: compare with the CID of `ipfs add -r` over the same tree
+    assert_eq!(hash.unwrap(), builder.build().unwrap());
+
+    // paths should not be duplicated
+    assert!(builder.add_bytes("small.txt", b"hello world").is_err());
+    assert!(builder.add_bytes("small.txt/file", b"hello world").is_err());
+}
+
+#[test]
+fn hash_directory_hamt() {
+    let mut builder = Hash::directory_builder();
+    for index in 0..8_192 {
+        builder
+            .add_bytes(format!("file-{index:08}.txt"), index.to_string().as_bytes())
+            .unwrap();
+    }
+    // FIXME: compare with the CID of `ipfs add -r` over the same entries
+    let hash = builder.build().unwrap();
+
+    // the entries should be sharded deterministically
+    let mut builder = Hash::directory_builder();
+    for index in (0..8_192).rev() {
+        builder
+            .add_bytes(format!("file-{index:08}.txt"), index.to_string().as_bytes())
+            .unwrap();
+    }
+    assert_eq!(hash, builder.build().unwrap());
+}
+
+#[test]
+fn hash_verifying_reader() {
+    let data = vec![42; 262_144 * 4 + 1];
+    let hash = Hash::with_bytes(&data);
+
+    // verify the valid content
+    let mut reader = VerifyingReader::new(&data[..], hash);
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).unwrap();
+    assert!(reader.is_verified());
+    assert_eq!(buf, data);
+
+    // detect the corruption at EOF
+    let mut corrupted = data.clone();
+    corrupted[1] = 0;
+    let mut reader = VerifyingReader::new(&corrupted[..], hash);
+    let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(!reader.is_verified());
+
+    // detect the corruption early with the leaf hashes
+    let leaves = data.chunks(262_144).map(Hash::with_bytes).collect();
+    let mut reader = VerifyingReader::with_leaves(&corrupted[..], hash, leaves);
+    let mut buf = [0; 262_144];
+    let mut num_read = 0;
+    let error = loop {
+        match reader.read(&mut buf) {
+            Ok(len) => num_read += len,
+            Err(error) => break error,
+        }
+    };
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(num_read < data.len());
+}
+
+#[test]
+fn hash_inline() {
+    let options = HashOptions::default().inline_threshold(32);
+
+    // inline the tiny payload
+    let data = b"hello world";
+    let hash = Hash::with_bytes_options(data, &options);
+    assert!(hash.is_inlined());
+    assert_eq!(hash.inlined_bytes(), Some(&data[..]));
+
+    // parse the inlined hash
+    let parsed: Hash = hash.to_string().parse().unwrap();
+    assert_eq!(parsed.inlined_bytes(), Some(&data[..]));
+
+    // inline with Hasher
+    let mut hasher = Hasher::with_options(options);
+    hasher.update(data);
+    assert_eq!(hasher.finalize(), hash);
+
+    // inline with ParallelHasher
+    let mut hasher = ParallelHasher::with_options(options);
+    hasher.update(data);
+    assert_eq!(hasher.finalize(), hash);
+
+    // skip inlining the larger payload
+    let data = &[0; 33];
+    let hash = Hash::with_bytes_options(data, &options);
+    assert!(!hash.is_inlined());
+    assert_eq!(hash.inlined_bytes(), None);
+    assert_eq!(hash, Hash::with_bytes(data));
+
+    // skip inlining by default
+    let data = b"hello world";
+    let hash = Hash::with_bytes_options(data, &HashOptions::default());
+    assert!(!hash.is_inlined());
+}
+
+#[test]
+fn hash_range_proof() {
+    let data: Vec<u8> = (0..262_144 * 174 + 262_144 * 3 + 1)
+        .map(|index| (index % 251) as u8)
+        .collect();
+    let hash = Hash::with_bytes(&data);
+
+    for range in [
+        0..1,
+        1_000..2_000,
+        262_144 - 1..262_144 + 1,
+        262_144 * 173..262_144 * 175,
+        data.len() - 1..data.len(),
+        0..data.len(),
+    ] {
+        // prove
+        let proof = Hash::prove_range(&data, range.clone()).unwrap();
+
+        // verify
+        let leaves_range = proof.leaves_range();
+        let leaves = &data[leaves_range.start as usize..leaves_range.end as usize];
+        assert_eq!(proof.verify(&hash, leaves).unwrap(), &data[range]);
+    }
+
+    // detect the corrupted leaves
+    let proof = Hash::prove_range(&data, 1_000..2_000).unwrap();
+    let mut leaves = data[..262_144].to_vec();
+    leaves[1_500] ^= 0xff;
+    assert!(proof.verify(&hash, &leaves).is_err());
+
+    // detect the corrupted nodes
+    let mut corrupted = proof.clone();
+    corrupted.nodes[0][8] ^= 0xff;
+    assert!(corrupted.verify(&hash, &data[..262_144]).is_err());
+
+    // detect the wrong root
+    let other = Hash::with_bytes(&data[1..]);
+    assert!(proof.verify(&other, &data[..262_144]).is_err());
+
+    // prove the unit chunk
+    let data = b"hello world";
+    let hash = Hash::with_bytes(data);
+    let proof = Hash::prove_range(data, 6..11).unwrap();
+    assert!(proof.nodes.is_empty());
+    assert_eq!(proof.verify(&hash, data).unwrap(), b"world");
+}
+
+#[test]
+fn hash_dag_pb_leaves() {
+    let options = HashOptions::default()
+        .leaf_encoding(LeafEncoding::DagPb)
+        .cid_version(Version::V0);
+
+    for (data, expected_cid) in [
+        // ipfs add --raw-leaves=false --cid-version=0
+        (&b""[..], "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"),
+        (
+            &b"hello world\n"[..],
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o",
+        ),
+    ] {
+        let hash = Hash::with_bytes_options(data, &options);
+        assert_eq!(hash.version(), Version::V0);
+        assert_eq!(hash.to_string(), expected_cid);
+
+        let mut hasher = Hasher::with_options(options);
+        hasher.update(data);
+        assert_eq!(hasher.finalize().to_string(), expected_cid);
+    }
+
+    // FIXME: these multi-chunk vectors are regression values produced by this crate,
+    //        not yet captured with `ipfs add --raw-leaves=false` (v0 and `--cid-version=1`);
+    //        replace them with the go-ipfs outputs of the same bytes
+    let data: Vec<u8> = (0..262_144 * 2 + 1)
+        .map(|index| (index % 251) as u8)
+        .collect();
+    for (options, expected_cid) in [
+        (options, "QmePkCgTbAcZgGDqVBd5SVyL7mRdv5ctvdmkCZSoercCej"),
+        (
+            HashOptions::default().leaf_encoding(LeafEncoding::DagPb),
+            "bafybeihlufjyzl742quz5xyomtgqsifplcuoiijemvu5dy2ttb2ew2k7ma",
+        ),
+    ] {
+        let hash = Hash::with_bytes_options(&data, &options);
+        assert_eq!(hash.to_string(), expected_cid);
+
+        let mut hasher = Hasher::with_options(options);
+        for chunk in data.chunks(1_000) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), hash);
+
+        let mut hasher = ParallelHasher::with_options(options);
+        hasher.update(&data);
+        assert_eq!(hasher.finalize(), hash);
+    }
+
+    // raw leaves by default
+    let data = b"hello world";
+    assert_eq!(
+        Hash::with_bytes_options(data, &HashOptions::default()),
+        Hash::with_bytes(data),
+    );
+}
+
+#[test]
+fn hash_archive() {
+    let data = &[0; 262_144 * 2];
+    let hashes = [
+        Hash::with_bytes(b"hello world"),
+        Hash::with_bytes(data),
+        // CIDv0
+        Hash::with_bytes(data).to_v0().unwrap(),
+        // identity multihash
+        Hash::with_bytes_options(b"hello world", &HashOptions::default().inline_threshold(32)),
+        // sha2-512 multihash
+        "bafkrgqbqt3gerhas23vuzrapkdeqf4vu2dwxp3srdj6hvg6nhsug2tgyn6mj3u23yx7utftq3i2ckw2fwdh5qmhid5qf3t35yvkc5e5ottlw6"
+            .parse()
+            .unwrap(),
+        Hash::default(),
+    ];
+
+    for hash in hashes {
+        let bytes = ::rkyv::to_bytes::<_, 256>(&hash).unwrap();
+        let archived = ::rkyv::check_archived_root::<Hash>(&bytes[..]).unwrap();
+        assert_eq!(archived, &hash);
+
+        let deserialized: Hash =
+            ::rkyv::Deserialize::deserialize(archived, &mut ::rkyv::Infallible).unwrap();
+        assert_eq!(deserialized, hash);
+        assert_eq!(deserialized.version(), hash.version());
+    }
+
+    // detect the malformed hash
+    let hash = Hash::with_bytes(b"hello world");
+    let mut bytes = ::rkyv::to_bytes::<_, 256>(&hash).unwrap();
+    bytes[0] += 1;
+    assert!(::rkyv::check_archived_root::<Hash>(&bytes[..]).is_err());
+
+    // detect the unparsable CID, which should never reach the deserializer
+    let mut bytes = ::rkyv::to_bytes::<_, 256>(&hash).unwrap();
+    bytes[1] = 0x05;
+    assert!(::rkyv::check_archived_root::<Hash>(&bytes[..]).is_err());
+}
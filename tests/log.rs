@@ -0,0 +1,145 @@
+use bytecheck::CheckBytes;
+use ipi::{
+    account::Account,
+    log::{find_forks, SignedLog},
+    signed::{IsSigned, SERIALIZER_HEAP_SIZE},
+};
+use rkyv::{de::deserializers::SharedDeserializeMap, Archive, Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct MyData {
+    pub msg: String,
+    pub num: u64,
+}
+
+impl IsSigned for MyData {}
+
+fn data(num: u64) -> MyData {
+    MyData {
+        msg: "Hello world!".to_string(),
+        num,
+    }
+}
+
+#[test]
+fn log_chain() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    let mut log = SignedLog::new();
+    assert!(log.head().is_none());
+
+    let first = log
+        .append(&guarantee, guarantor.account_ref(), data(1))
+        .unwrap();
+    let second = log
+        .append(&guarantee, guarantor.account_ref(), data(2))
+        .unwrap();
+    let third = log
+        .append(&guarantee, guarantor.account_ref(), data(3))
+        .unwrap();
+    log.verify().unwrap();
+
+    // look up the entries
+    assert_eq!(log.len(), 3);
+    assert_eq!(log.head_hash().unwrap(), Some(third));
+    assert_eq!(log.head().unwrap().data.data.data, data(3));
+    assert_eq!(log.entries()[0].parent(), None);
+    assert_eq!(log.get(&second).unwrap().unwrap().parent(), Some(first));
+
+    // countersign as guarantor, keeping the hashes
+    log.countersign(&guarantor, &second).unwrap();
+    assert!(log.countersign(&guarantee, &third).is_err());
+    assert_eq!(log.entries()[1].to_hash().unwrap(), second);
+    assert!(log.entries()[1].to_guarantor_signed().is_some());
+    log.verify().unwrap();
+
+    // archive
+    let bytes = ::rkyv::to_bytes::<_, SERIALIZER_HEAP_SIZE>(&log).unwrap();
+    let archived = ::rkyv::check_archived_root::<SignedLog<MyData>>(&bytes[..]).unwrap();
+    let restored: SignedLog<MyData> =
+        Deserialize::deserialize(archived, &mut SharedDeserializeMap::default()).unwrap();
+    assert_eq!(restored, log);
+    restored.verify().unwrap();
+
+    // look up the entries of the restored log, including the appended ones
+    let mut restored = restored;
+    assert_eq!(restored.get(&first).unwrap().unwrap().parent(), None);
+    let fourth = restored
+        .append(&guarantee, guarantor.account_ref(), data(4))
+        .unwrap();
+    assert_eq!(
+        restored.get(&fourth).unwrap().unwrap().parent(),
+        Some(third)
+    );
+
+    // detect the removed entries
+    let mut entries = log.entries().to_vec();
+    entries.remove(1);
+    assert!(SignedLog::from_entries(entries).is_err());
+
+    // detect the tampered entries
+    let mut entries = log.entries().to_vec();
+    entries[0].data.data.data.num = 42;
+    assert!(SignedLog::from_entries(entries).is_err());
+
+    // detect the forged countersignatures
+    let mut entries = log.entries().to_vec();
+    entries[2].countersignature = entries[1].countersignature;
+    assert!(SignedLog::from_entries(entries).is_err());
+
+    assert_eq!(
+        SignedLog::from_entries(log.entries().to_vec()).unwrap(),
+        log
+    );
+}
+
+#[test]
+fn log_fork() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    let mut ours = SignedLog::new();
+    let parent = ours
+        .append(&guarantee, guarantor.account_ref(), data(1))
+        .unwrap();
+
+    // diverge the replicas
+    let mut theirs = ours.clone();
+    let our_head = ours
+        .append(&guarantee, guarantor.account_ref(), data(2))
+        .unwrap();
+    let their_head = theirs
+        .append(&guarantee, guarantor.account_ref(), data(3))
+        .unwrap();
+    assert!(ours.find_fork(&ours).unwrap().is_none());
+
+    let fork = ours.find_fork(&theirs).unwrap().unwrap();
+    assert_eq!(fork.parent, Some(parent));
+    let mut children = vec![our_head, their_head];
+    children.sort();
+    assert_eq!(fork.children, children);
+    assert_eq!(
+        find_forks(ours.entries().iter().chain(theirs.entries())).unwrap(),
+        vec![fork],
+    );
+
+    // reject the entries sharing a parent
+    let entry = theirs.head().unwrap().clone();
+    let error = ours.append_entry(entry).unwrap_err();
+    assert!(error.to_string().contains("fork"));
+    assert_eq!(ours.head_hash().unwrap(), Some(our_head));
+
+    // accept the entries chained to the head
+    let mut replica = ours.clone();
+    let head = replica
+        .append(&guarantee, guarantor.account_ref(), data(4))
+        .unwrap();
+    ours.append_entry(replica.head().unwrap().clone()).unwrap();
+    assert_eq!(ours.head_hash().unwrap(), Some(head));
+    ours.verify().unwrap();
+}
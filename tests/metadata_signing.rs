@@ -73,6 +73,7 @@ fn test_strict() {
         expiration_date: None,
         guarantor: account.account_ref(),
         hash: Hash::with_bytes(&42i32.to_le_bytes()),
+        schema: None,
     };
 
     let signed = ::ipi::account::GuaranteeSigned::sign(&account, metadata).unwrap();
@@ -80,16 +81,91 @@ fn test_strict() {
 
     let bytes = &[
         178, 127, 84, 7, 76, 6, 240, 252, 66, 76, 107, 153, 78, 227, 199, 47, 255, 205, 198, 205,
-        169, 240, 131, 27, 107, 97, 3, 20, 99, 143, 106, 117, 255, 225, 49, 219, 5, 212, 64, 229,
-        207, 71, 246, 10, 236, 109, 87, 32, 36, 160, 76, 140, 143, 190, 6, 135, 39, 174, 87, 122,
-        208, 76, 224, 33, 146, 145, 48, 198, 150, 55, 119, 139, 184, 22, 221, 73, 184, 228, 4, 211,
-        247, 210, 117, 148, 29, 127, 249, 246, 8, 194, 165, 23, 65, 29, 140, 0, 0, 0, 68, 85, 102,
+        169, 240, 131, 27, 107, 97, 3, 20, 99, 143, 106, 117, 80, 134, 152, 47, 40, 90, 85, 129,
+        193, 60, 11, 246, 69, 162, 229, 0, 64, 146, 213, 111, 29, 42, 214, 202, 225, 135, 65, 75,
+        52, 145, 138, 105, 162, 53, 169, 59, 104, 150, 9, 131, 68, 67, 40, 183, 224, 72, 65, 54, 3,
+        41, 227, 25, 24, 104, 120, 132, 113, 175, 137, 185, 221, 86, 208, 14, 0, 0, 68, 85, 102,
         68, 22, 167, 212, 65, 155, 226, 0, 132, 14, 85, 234, 181, 250, 24, 0, 0, 0, 0, 128, 232,
         84, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
         178, 127, 84, 7, 76, 6, 240, 252, 66, 76, 107, 153, 78, 227, 199, 47, 255, 205, 198, 205,
-        169, 240, 131, 27, 107, 97, 3, 20, 99, 143, 106, 117, 1, 85, 18, 32, 232, 164, 178, 238,
-        126, 222, 121, 163, 175, 179, 50, 181, 182, 204, 61, 149, 42, 101, 253, 140, 255, 184, 151,
-        245, 209, 128, 22, 87, 124, 51, 215, 204, 0, 0, 0, 0,
+        169, 240, 131, 27, 107, 97, 3, 20, 99, 143, 106, 117, 36, 1, 85, 18, 32, 232, 164, 178,
+        238, 126, 222, 121, 163, 175, 179, 50, 181, 182, 204, 61, 149, 42, 101, 253, 140, 255, 184,
+        151, 245, 209, 128, 22, 87, 124, 51, 215, 204, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     ];
     assert_eq!(signed.as_slice(), bytes);
 }
+
+#[test]
+fn test_deterministic() {
+    use ipi::{
+        account::GuaranteeSigned,
+        metadata::Metadata,
+        value::{chrono::DateTime, nonce::Nonce},
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let account = Account::generate();
+    let nonce: Nonce = "550e8400-e29b-41d4-a716-446655440000".parse().unwrap();
+    let created_date = DateTime(
+        ::ipi::chrono::DateTime::parse_from_rfc3339("1983-04-13T12:09:14.274Z")
+            .unwrap()
+            .with_timezone(&::ipi::chrono::Utc),
+    );
+
+    // inject the nonce and the created date, as the hand-made metadata
+    let signed = Metadata::builder()
+        .nonce(nonce)
+        .created_date(created_date)
+        .build(&account, account.account_ref(), &42i32)
+        .unwrap();
+    let metadata = Metadata {
+        nonce,
+        created_date,
+        expiration_date: None,
+        guarantor: account.account_ref(),
+        hash: signed.hash,
+        schema: None,
+    };
+    assert_eq!(signed, GuaranteeSigned::sign(&account, metadata).unwrap());
+
+    // inject the clock and the RNG source
+    let build = |seed| {
+        Data::builder()
+            .clock(move || created_date)
+            .rng(StdRng::seed_from_u64(seed))
+            .build_owned(&account, account.account_ref(), 42i32)
+            .unwrap()
+    };
+    assert_eq!(build(42), build(42));
+    assert_ne!(build(42), build(43));
+    assert_eq!(build(42).metadata.created_date, created_date);
+    assert_eq!(build(42).metadata.nonce.get_version_num(), 4);
+
+    // the injected values take precedence over the sources
+    let signed = Data::builder()
+        .nonce(nonce)
+        .rng(StdRng::seed_from_u64(42))
+        .build_owned(&account, account.account_ref(), 42i32)
+        .unwrap();
+    assert_eq!(signed.metadata.nonce, nonce);
+
+    // the builder can be moved across threads along with its sources
+    let schema = Hash::with_bytes(b"schema");
+    let builder = Data::builder()
+        .schema(schema)
+        .clock(move || created_date)
+        .rng(StdRng::seed_from_u64(42));
+    let signed = ::std::thread::spawn(move || {
+        builder
+            .build_owned(&account, account.account_ref(), 42i32)
+            .unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(signed.metadata.schema, Some(schema));
+}
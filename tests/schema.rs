@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use ipi::value::{
+    schema::{Field, Schema, Violation},
+    text::Text,
+    Value, ValueType,
+};
+
+fn schema() -> Schema {
+    Schema::object([
+        (
+            "name".to_string(),
+            Field::required(Schema::Text {
+                langs: vec!["en".parse().unwrap()],
+            }),
+        ),
+        (
+            "age".to_string(),
+            Field::required(Schema::Range {
+                ty: ValueType::U8,
+                min: None,
+                max: Some(150.0),
+            }),
+        ),
+        (
+            "role".to_string(),
+            Field::optional(Schema::Enum(vec![
+                Value::Text(Text::with_en_us("admin")),
+                Value::Text(Text::with_en_us("user")),
+            ])),
+        ),
+        (
+            "tags".to_string(),
+            Field::optional(Schema::List(Box::new(Schema::Type(ValueType::Text)))),
+        ),
+        (
+            "address".to_string(),
+            Field::optional(Schema::object([(
+                "city".to_string(),
+                Field::required(Schema::Type(ValueType::Text)),
+            )])),
+        ),
+    ])
+}
+
+fn text(msg: &str) -> Value {
+    Value::Text(Text::with_en_us(msg))
+}
+
+#[test]
+fn schema_validate() {
+    let schema = schema();
+
+    let value = Value::Map(BTreeMap::from([
+        ("name".to_string(), text("Alice")),
+        ("age".to_string(), Value::U8(42)),
+        ("role".to_string(), text("admin")),
+        (
+            "tags".to_string(),
+            Value::List(vec![text("foo"), text("bar")]),
+        ),
+        (
+            "address".to_string(),
+            Value::Map(BTreeMap::from([("city".to_string(), text("Seoul"))])),
+        ),
+    ]));
+    assert!(schema.violations(&value).is_empty());
+    assert!(schema.validate(&value).is_ok());
+
+    let value = Value::Map(BTreeMap::from([
+        ("age".to_string(), Value::U8(200)),
+        ("role".to_string(), text("root")),
+        (
+            "tags".to_string(),
+            Value::List(vec![text("foo"), Value::U8(1)]),
+        ),
+        ("address".to_string(), Value::Map(Default::default())),
+        ("unknown".to_string(), Value::None),
+    ]));
+    let violations = schema.violations(&value);
+    assert_eq!(
+        violations
+            .iter()
+            .map(|Violation { path, .. }| path.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "$.address.city",
+            "$.age",
+            "$.name",
+            "$.role",
+            "$.tags[1]",
+            "$.unknown",
+        ],
+    );
+    assert!(schema.validate(&value).is_err());
+
+    // reject the other languages
+    let value = Value::Text(Text {
+        msg: "안녕".to_string(),
+        lang: "ko".parse().unwrap(),
+    });
+    assert_eq!(
+        Schema::Text {
+            langs: vec!["en".parse().unwrap()],
+        }
+        .violations(&value),
+        [Violation {
+            path: "$".to_string(),
+            message: "unexpected language: ko".to_string(),
+        }],
+    );
+}
+
+#[test]
+fn schema_hash() {
+    let schema = schema();
+    let hash = schema.to_hash().unwrap();
+
+    // the same schema has the same hash
+    assert_eq!(schema.clone().to_hash().unwrap(), hash);
+
+    // the different schema has the different hash
+    let other = Schema::List(Box::new(schema.clone()));
+    assert_ne!(other.to_hash().unwrap(), hash);
+
+    // archive the schema
+    let bytes = ::rkyv::to_bytes::<_, 4096>(&schema).unwrap();
+    let archived = ::rkyv::check_archived_root::<Schema>(&bytes[..]).unwrap();
+    assert_eq!(archived, &schema);
+
+    // refer the schema from the metadata
+    let account = ::ipi::account::Account::generate();
+    let metadata = ::ipi::metadata::Metadata::builder()
+        .schema(hash)
+        .build_unsigned_raw(
+            account.account_ref(),
+            ::ipi::value::hash::Hash::with_str("hello"),
+        );
+    assert_eq!(metadata.schema, Some(hash));
+}
+
+#[test]
+fn schema_range_integer() {
+    let schema = Schema::Range {
+        ty: ValueType::U64,
+        min: Some(0.5),
+        max: Some((1u64 << 53) as f64),
+    };
+
+    // compare the integers exactly, beyond the precision of f64
+    assert!(schema.violations(&Value::U64(1 << 53)).is_empty());
+    assert_eq!(schema.violations(&Value::U64((1 << 53) + 1)).len(), 1);
+    assert_eq!(schema.violations(&Value::U64(u64::MAX)).len(), 1);
+
+    // round the fractional bounds inward
+    assert_eq!(schema.violations(&Value::U64(0)).len(), 1);
+    assert!(schema.violations(&Value::U64(1)).is_empty());
+
+    // the bounds beyond the integers
+    let schema = Schema::Range {
+        ty: ValueType::I64,
+        min: Some(-1e300),
+        max: Some(1e300),
+    };
+    assert!(schema.violations(&Value::I64(i64::MIN)).is_empty());
+    assert!(schema.violations(&Value::I64(i64::MAX)).is_empty());
+}
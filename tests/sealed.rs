@@ -0,0 +1,215 @@
+use bytecheck::CheckBytes;
+use ipi::{
+    account::{Account, GuarantorSigned, Verifier},
+    data::Data,
+    sealed::{Commitment, Sealed},
+    signed::{IsSigned, SERIALIZER_HEAP_SIZE},
+};
+use rkyv::{de::deserializers::SharedDeserializeMap, Archive, Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct MyData {
+    pub msg: String,
+    pub num: u64,
+}
+
+impl IsSigned for MyData {}
+
+fn data() -> MyData {
+    MyData {
+        msg: "Hello world!".to_string(),
+        num: 42,
+    }
+}
+
+#[test]
+fn sealed_recipients() {
+    let alice = Account::generate();
+    let bob = Account::generate();
+    let eve = Account::generate();
+
+    let sealed = Sealed::seal(
+        &data(),
+        &[alice.account_ref(), bob.account_ref()],
+        Commitment::Ciphertext,
+    )
+    .unwrap();
+    assert!(sealed.is_recipient(&alice.account_ref()));
+    assert!(!sealed.is_recipient(&eve.account_ref()));
+
+    // every recipient can open it
+    assert_eq!(alice.open::<MyData>(&sealed).unwrap(), data());
+    assert_eq!(bob.open::<MyData>(&sealed).unwrap(), data());
+
+    // but the others cannot
+    assert!(eve.open::<MyData>(&sealed).is_err());
+
+    // pretending to be a recipient does not help
+    let mut forged = sealed.clone();
+    forged.recipients[0].recipient = eve.account_ref();
+    assert!(eve.open::<MyData>(&forged).is_err());
+
+    // detect the tampered ciphertext
+    let mut tampered = sealed.clone();
+    tampered.ciphertext[0] ^= 1;
+    assert!(alice.open::<MyData>(&tampered).is_err());
+
+    // detect the tampered commitment
+    let mut tampered = sealed.clone();
+    tampered.commitment = Commitment::Plaintext;
+    assert!(alice.open::<MyData>(&tampered).is_err());
+
+    // detect the tampered key commitment, which the hash covers
+    let mut tampered = sealed.clone();
+    tampered.key_commitment[0] ^= 1;
+    assert!(alice.open::<MyData>(&tampered).is_err());
+    assert_ne!(tampered.to_hash(), sealed.to_hash());
+
+    // detect another content key, wrapped for one of the recipients
+    let other = Sealed::seal(&data(), &[bob.account_ref()], Commitment::Ciphertext).unwrap();
+    let mut forged = sealed;
+    let index = forged
+        .recipients
+        .iter()
+        .position(|wrapped| wrapped.recipient == bob.account_ref())
+        .unwrap();
+    forged.recipients[index] = other.recipients[0];
+    assert!(bob.open::<MyData>(&forged).is_err());
+
+    // seal to nobody
+    assert!(Sealed::seal(&data(), &[], Commitment::Ciphertext).is_err());
+}
+
+#[test]
+fn sealed_add_recipients() {
+    let alice = Account::generate();
+    let bob = Account::generate();
+    let carol = Account::generate();
+    let eve = Account::generate();
+
+    let mut sealed = Sealed::seal(
+        &data(),
+        &[bob.account_ref(), alice.account_ref(), bob.account_ref()],
+        Commitment::Ciphertext,
+    )
+    .unwrap();
+    assert_eq!(sealed.recipients.len(), 2);
+    assert!(eve.open::<MyData>(&sealed).is_err());
+
+    // look up the recipients without deserializing
+    let bytes = ::rkyv::to_bytes::<_, SERIALIZER_HEAP_SIZE>(&sealed).unwrap();
+    let archived = ::rkyv::check_archived_root::<Sealed>(&bytes[..]).unwrap();
+    assert!(archived.recipient(&alice.account_ref()).is_some());
+    assert!(archived.recipient(&eve.account_ref()).is_none());
+
+    // only the recipients can add the others
+    let ciphertext = sealed.ciphertext.clone();
+    assert!(sealed.add_recipients(&eve, &[eve.account_ref()]).is_err());
+    sealed
+        .add_recipients(&bob, &[carol.account_ref(), alice.account_ref()])
+        .unwrap();
+    assert_eq!(sealed.recipients.len(), 3);
+    assert!(sealed
+        .recipients
+        .windows(2)
+        .all(|pair| pair[0].recipient < pair[1].recipient));
+
+    // leave the recipients as is if any key cannot be wrapped
+    let low_order = "4uQeVj5tqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM"
+        .parse()
+        .unwrap();
+    assert!(sealed
+        .add_recipients(&bob, &[eve.account_ref(), low_order])
+        .is_err());
+    assert_eq!(sealed.recipients.len(), 3);
+
+    // the body is not re-encrypted
+    assert_eq!(sealed.ciphertext, ciphertext);
+    for account in [&alice, &bob, &carol] {
+        assert_eq!(account.open::<MyData>(&sealed).unwrap(), data());
+    }
+    assert!(eve.open::<MyData>(&sealed).is_err());
+}
+
+#[test]
+fn sealed_data() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+    let recipient = Account::generate();
+
+    let data = data();
+
+    for commitment in [Commitment::Plaintext, Commitment::Ciphertext] {
+        // seal and sign as guarantee
+        let signed = Data::builder()
+            .build_sealed(
+                &guarantee,
+                guarantor.account_ref(),
+                &data,
+                &[recipient.account_ref()],
+                commitment,
+            )
+            .unwrap();
+
+        // the plaintext commitment matches the unsealed data
+        let plain = Data::builder()
+            .build(&guarantee, guarantor.account_ref(), &data)
+            .unwrap();
+        assert_eq!(
+            signed.metadata.hash == plain.metadata.hash,
+            commitment == Commitment::Plaintext,
+        );
+
+        // sign as guarantor
+        let signed = signed.sign(&guarantor).unwrap();
+        signed.verify(Some(&guarantor.account_ref())).unwrap();
+
+        // archive
+        let bytes = ::rkyv::to_bytes::<_, SERIALIZER_HEAP_SIZE>(&signed).unwrap();
+        let archived =
+            ::rkyv::check_archived_root::<Data<GuarantorSigned, Sealed>>(&bytes[..]).unwrap();
+        let signed: Data<GuarantorSigned, Sealed> =
+            Deserialize::deserialize(archived, &mut SharedDeserializeMap::default()).unwrap();
+
+        // open as recipient
+        let opened = signed.open::<MyData>(&recipient).unwrap();
+        assert_eq!(opened.data, data);
+        opened.verify(Some(&guarantor.account_ref())).unwrap();
+        assert!(signed.open::<MyData>(&guarantor).is_err());
+
+        // add the recipients, keeping the signatures
+        let mut added = signed.clone();
+        added
+            .data
+            .add_recipients(&recipient, &[guarantor.account_ref()])
+            .unwrap();
+        let opened = added.open::<MyData>(&guarantor).unwrap();
+        assert_eq!(opened.data, data);
+        opened.verify(Some(&guarantor.account_ref())).unwrap();
+
+        // detect the sealed data swapped by the others
+        let mut swapped = signed.clone();
+        swapped.data =
+            Sealed::seal(&data, &[recipient.account_ref()], swapped.data.commitment).unwrap();
+        let result = swapped.open::<MyData>(&recipient);
+        match commitment {
+            Commitment::Plaintext => assert!(result.is_ok()),
+            Commitment::Ciphertext => assert!(result.is_err()),
+        }
+
+        // detect the sealed data of the other payloads
+        swapped.data = Sealed::seal(
+            &MyData {
+                msg: "Goodbye world!".to_string(),
+                num: 42,
+            },
+            &[recipient.account_ref()],
+            swapped.data.commitment,
+        )
+        .unwrap();
+        assert!(swapped.open::<MyData>(&recipient).is_err());
+    }
+}
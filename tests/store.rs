@@ -0,0 +1,386 @@
+use std::collections::BTreeSet;
+
+use bytecheck::CheckBytes;
+use ipi::{
+    account::{Account, GuaranteeSigned, GuarantorSigned},
+    data::{Data, DataBuilder},
+    sealed::{Commitment, Sealed},
+    signed::IsSigned,
+    store::{FsStore, IndexedStore, MemoryStore, Query, Store},
+    value::{chrono::DateTime, hash::Hash, text::Text, Value},
+};
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive(compare(PartialEq))]
+#[archive_attr(derive(CheckBytes, Debug, PartialEq))]
+pub struct MyData {
+    pub msg: String,
+    pub num: u64,
+}
+
+impl IsSigned for MyData {}
+
+fn signed(guarantee: &Account, guarantor: &Account, num: u64) -> Data<GuarantorSigned, MyData> {
+    signed_with(Data::builder(), guarantee, guarantor, num)
+}
+
+fn signed_with(
+    builder: DataBuilder<MyData>,
+    guarantee: &Account,
+    guarantor: &Account,
+    num: u64,
+) -> Data<GuarantorSigned, MyData> {
+    builder
+        .build_owned(
+            guarantee,
+            guarantor.account_ref(),
+            MyData {
+                msg: "Hello world!".to_string(),
+                num,
+            },
+        )
+        .unwrap()
+        .sign(guarantor)
+        .unwrap()
+}
+
+fn test_store(store: &impl Store) {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+
+    let first = signed(&guarantee, &guarantor, 42);
+    let second = signed(&guarantee, &guarantor, 43);
+
+    // put the data, idempotently
+    let hash = store.put(&first).unwrap();
+    assert_eq!(store.put(&first).unwrap(), hash);
+    let other = store.put(&second).unwrap();
+    assert_ne!(hash, other);
+    assert!(store.contains(&hash).unwrap());
+
+    let mut hashes = vec![hash, other];
+    hashes.sort();
+    assert_eq!(store.list().unwrap(), hashes);
+
+    // access the archived data zero-copy
+    let object = store.get(&hash).unwrap().unwrap();
+    assert_eq!(Hash::with_bytes(&object), hash);
+    let archived = object.archived::<Data<GuarantorSigned, MyData>>().unwrap();
+    assert_eq!(archived, &first);
+
+    // get the data back
+    assert_eq!(
+        store
+            .get_data::<GuarantorSigned, MyData>(&hash)
+            .unwrap()
+            .unwrap(),
+        first,
+    );
+    assert!(store.get_data::<GuaranteeSigned, MyData>(&hash).is_err());
+
+    // verify on write
+    let mut forged = second;
+    forged.metadata.data.data.hash = Hash::with_bytes(b"forged");
+    assert!(store.put(&forged).is_err());
+
+    // verify the payload against the signed hash
+    let mut swapped = signed(&guarantee, &guarantor, 44);
+    swapped.data = first.data.clone();
+    assert!(store.put(&swapped).is_err());
+
+    // remove all but the live ones
+    let live = BTreeSet::from([hash]);
+    let removed = store.gc(&live).unwrap();
+    assert_eq!(removed, vec![other]);
+    assert_eq!(store.list().unwrap(), vec![hash]);
+
+    // access the bytes through the trait object
+    let object_store: &dyn Store = store;
+    assert!(object_store.contains(&hash).unwrap());
+
+    // remove the data
+    assert!(store.remove(&hash).unwrap());
+    assert!(!store.remove(&hash).unwrap());
+    assert!(store.get(&hash).unwrap().is_none());
+    assert!(store.list().unwrap().is_empty());
+}
+
+#[test]
+fn store_memory() {
+    test_store(&MemoryStore::new());
+}
+
+#[test]
+fn store_fs() {
+    let dir = ::tempfile::tempdir().unwrap();
+    let store = FsStore::open(dir.path()).unwrap();
+    test_store(&store);
+
+    // persist across the stores
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+    let data = signed(&guarantee, &guarantor, 42);
+    let hash = store.put(&data).unwrap();
+
+    let store = FsStore::open(dir.path()).unwrap();
+    assert_eq!(store.list().unwrap(), vec![hash]);
+    assert_eq!(
+        store
+            .get_data::<GuarantorSigned, MyData>(&hash)
+            .unwrap()
+            .unwrap(),
+        data,
+    );
+
+    // clean up the interrupted writes
+    let tmp = dir.path().join("tmp").join("interrupted");
+    ::std::fs::write(&tmp, b"partial").unwrap();
+    assert_eq!(store.list().unwrap(), vec![hash]);
+    assert!(store.gc(&BTreeSet::from([hash])).unwrap().is_empty());
+    assert!(!tmp.exists());
+}
+
+#[test]
+fn store_put_envelopes() {
+    let store = MemoryStore::new();
+
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+    let recipient = Account::generate();
+
+    // the plaintext commitment is checked on opening, not on writing
+    let data = MyData {
+        msg: "Hello world!".to_string(),
+        num: 42,
+    };
+    for commitment in [Commitment::Plaintext, Commitment::Ciphertext] {
+        let sealed = Data::builder()
+            .build_sealed(
+                &guarantee,
+                guarantor.account_ref(),
+                &data,
+                &[recipient.account_ref()],
+                commitment,
+            )
+            .unwrap()
+            .sign(&guarantor)
+            .unwrap();
+        let hash = store.put(&sealed).unwrap();
+
+        let stored = store
+            .get_data::<GuarantorSigned, Sealed>(&hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.open::<MyData>(&recipient).unwrap().data, data);
+
+        let mut swapped = sealed;
+        swapped.data.ciphertext[0] ^= 1;
+        assert_eq!(
+            store.put(&swapped).is_ok(),
+            commitment == Commitment::Plaintext,
+        );
+    }
+
+    // the data signed over the DAG-CBOR bytes
+    let value = Value::Text(Text::with_en_us("Hello world!"));
+    let signed = Data::builder()
+        .build_owned_dag_cbor(&guarantee, guarantor.account_ref(), value)
+        .unwrap()
+        .sign_dag_cbor(&guarantor)
+        .unwrap();
+    assert!(store.put(&signed).is_err());
+    let hash = store.put_dag_cbor(&signed).unwrap();
+    assert_eq!(
+        store
+            .get_data::<GuarantorSigned, Value>(&hash)
+            .unwrap()
+            .unwrap(),
+        signed,
+    );
+
+    let mut swapped = signed;
+    swapped.data = Value::Text(Text::with_en_us("Goodbye world!"));
+    assert!(store.put_dag_cbor(&swapped).is_err());
+}
+
+fn after(hours: i64) -> DateTime {
+    DateTime(::ipi::chrono::Utc::now() + ::ipi::chrono::Duration::hours(hours))
+}
+
+#[test]
+fn store_index() {
+    let dir = ::tempfile::tempdir().unwrap();
+    let store =
+        IndexedStore::open::<GuarantorSigned, MyData>(FsStore::open(dir.path()).unwrap()).unwrap();
+
+    let alice = Account::generate();
+    let bob = Account::generate();
+    let carol = Account::generate();
+
+    let start = DateTime::now();
+    let first = store.put(&signed(&alice, &bob, 1)).unwrap();
+    let second = store
+        .put(&signed_with(
+            Data::builder().expiration_date(after(1)),
+            &carol,
+            &bob,
+            2,
+        ))
+        .unwrap();
+    let middle = DateTime::now();
+    let third = store
+        .put(&signed_with(
+            Data::builder().expiration_date(after(2)),
+            &alice,
+            &carol,
+            3,
+        ))
+        .unwrap();
+    let end = after(0);
+
+    // guaranteed by the guarantor
+    assert_eq!(
+        store
+            .query(&Query::new().guarantor(bob.account_ref()))
+            .unwrap(),
+        vec![first, second],
+    );
+    assert_eq!(
+        store
+            .query(&Query::new().signed_guarantor(carol.account_ref()))
+            .unwrap(),
+        vec![third],
+    );
+    assert_eq!(
+        store
+            .query(&Query::new().guarantee(alice.account_ref()))
+            .unwrap(),
+        vec![first, third],
+    );
+
+    // between the dates
+    assert_eq!(
+        store
+            .query(&Query::new().created_between(start, end))
+            .unwrap(),
+        vec![first, second, third],
+    );
+    assert_eq!(
+        store
+            .query(
+                &Query::new()
+                    .guarantor(bob.account_ref())
+                    .created_between(middle, end)
+            )
+            .unwrap(),
+        vec![],
+    );
+    assert_eq!(
+        store
+            .query(
+                &Query::new()
+                    .guarantee(alice.account_ref())
+                    .created_between(middle, end)
+            )
+            .unwrap(),
+        vec![third],
+    );
+
+    // expiring before the date
+    assert_eq!(
+        store
+            .query(&Query::new().expiring_before(after(3)))
+            .unwrap(),
+        vec![second, third],
+    );
+    assert_eq!(
+        store
+            .query(
+                &Query::new()
+                    .guarantor(bob.account_ref())
+                    .expiring_before(after(3))
+            )
+            .unwrap(),
+        vec![second],
+    );
+
+    // reject the expired data
+    assert!(store
+        .put(&signed_with(
+            Data::builder().expiration_date(after(-1)),
+            &alice,
+            &bob,
+            4,
+        ))
+        .is_err());
+
+    // reindex the stored data, pruning the expired ones
+    let expired = store
+        .store()
+        .put(&signed_with(
+            Data::builder().expiration_date(after(-1)),
+            &alice,
+            &bob,
+            4,
+        ))
+        .unwrap();
+    let store = IndexedStore::open::<GuarantorSigned, MyData>(store.into_inner()).unwrap();
+    assert!(store.get_entry(&expired).unwrap().is_none());
+    assert!(!store.store().contains(&expired).unwrap());
+    assert_eq!(
+        store.get_entry(&second).unwrap().unwrap().guarantee,
+        carol.account_ref(),
+    );
+    assert_eq!(
+        store
+            .query(&Query::new().created_between(start, end))
+            .unwrap(),
+        vec![first, second, third],
+    );
+
+    // hide the data expired in the store, and prune it on the next write
+    let expiring = store
+        .put(&signed_with(
+            Data::builder().expiration_date(DateTime(
+                ::ipi::chrono::Utc::now() + ::ipi::chrono::Duration::milliseconds(100),
+            )),
+            &alice,
+            &bob,
+            5,
+        ))
+        .unwrap();
+    ::std::thread::sleep(::std::time::Duration::from_millis(200));
+    assert!(store.get_entry(&expiring).unwrap().is_none());
+    assert!(store
+        .get_data::<GuarantorSigned, MyData>(&expiring)
+        .unwrap()
+        .is_none());
+    assert!(store.store().contains(&expiring).unwrap());
+
+    let written = store.put(&signed(&carol, &bob, 6)).unwrap();
+    assert!(!store.store().contains(&expiring).unwrap());
+    assert!(store.remove(&written).unwrap());
+
+    // prune the expired data on demand
+    assert_eq!(store.prune_expired(after(1)).unwrap(), vec![second]);
+    assert!(store.get_entry(&second).unwrap().is_none());
+    assert!(store.store().get(&second).unwrap().is_none());
+    assert_eq!(store.store().list().unwrap().len(), 2);
+    assert_eq!(
+        store
+            .query(&Query::new().expiring_before(after(3)))
+            .unwrap(),
+        vec![third],
+    );
+
+    // remove the data
+    assert!(store.remove(&first).unwrap());
+    assert_eq!(
+        store
+            .query(&Query::new().guarantee(alice.account_ref()))
+            .unwrap(),
+        vec![third],
+    );
+}
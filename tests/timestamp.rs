@@ -0,0 +1,103 @@
+use ipi::{
+    account::{Account, Verifier},
+    chrono::Duration,
+    metadata::Metadata,
+    timestamp::{LocalTimestampAuthority, TimestampAuthority, TimestampPolicy},
+    value::{chrono::DateTime, hash::Hash},
+};
+
+#[test]
+fn timestamp_attestation() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+    let authority = LocalTimestampAuthority::generate();
+
+    let signed = Metadata::builder()
+        .build(&guarantee, guarantor.account_ref(), &42u64)
+        .unwrap();
+
+    // countersign as authority
+    let attestation = authority.timestamp(&signed).unwrap();
+    attestation.verify(Some(&authority.account_ref())).unwrap();
+    assert!(attestation.verify(Some(&guarantor.account_ref())).is_err());
+
+    let mut forged = attestation;
+    forged.data.time = DateTime(forged.time.0 - Duration::hours(1));
+    assert!(forged.verify(None).is_err());
+}
+
+#[test]
+fn timestamp_policy() {
+    // create client pair
+    let guarantee = Account::generate();
+    let guarantor = Account::generate();
+    let authority = LocalTimestampAuthority::generate();
+    let untrusted = LocalTimestampAuthority::generate();
+
+    let signed = Metadata::builder()
+        .build(&guarantee, guarantor.account_ref(), &42u64)
+        .unwrap();
+    let created_date = signed.created_date.0;
+    let attestation = authority.timestamp(&signed).unwrap();
+
+    // accept anything unless required
+    let policy = TimestampPolicy::new().trust(authority.account_ref());
+    policy.verify(&signed, &[]).unwrap();
+    policy.verify(&signed, &[attestation]).unwrap();
+
+    let policy = policy.required();
+    assert!(policy.verify(&signed, &[]).is_err());
+    policy.verify(&signed, &[attestation]).unwrap();
+
+    // ignore the untrusted authorities
+    let other = untrusted.timestamp(&signed).unwrap();
+    assert!(policy.verify(&signed, &[other]).is_err());
+    policy.verify(&signed, &[other, attestation]).unwrap();
+
+    // compare to the created date
+    let early = authority
+        .timestamp_at(&signed, DateTime(created_date - Duration::minutes(2)))
+        .unwrap();
+    assert!(policy.verify(&signed, &[early]).is_err());
+    policy
+        .clone()
+        .max_clock_skew(Duration::minutes(3))
+        .verify(&signed, &[early])
+        .unwrap();
+
+    let late = authority
+        .timestamp_at(&signed, DateTime(created_date + Duration::hours(1)))
+        .unwrap();
+    assert!(policy.verify(&signed, &[late]).is_err());
+    policy
+        .clone()
+        .max_delay(Duration::hours(2))
+        .verify(&signed, &[late])
+        .unwrap();
+
+    // do not overflow near the max date
+    let signed_max = Metadata::builder()
+        .created_date(DateTime::MAX_DATETIME)
+        .build(&guarantee, guarantor.account_ref(), &42u64)
+        .unwrap();
+    let at_max = authority
+        .timestamp_at(&signed_max, DateTime::MAX_DATETIME)
+        .unwrap();
+    policy.verify(&signed_max, &[at_max]).unwrap();
+    let now = authority.timestamp(&signed_max).unwrap();
+    assert!(policy.verify(&signed_max, &[now]).is_err());
+    policy
+        .clone()
+        .max_clock_skew(Duration::milliseconds(i64::MAX))
+        .max_delay(Duration::milliseconds(i64::MAX))
+        .verify(&signed, &[early, late])
+        .unwrap();
+
+    // detect the attestations of the other metadata
+    let mut other = signed.data;
+    other.hash = Hash::with_bytes(b"forged");
+    assert!(policy.verify(&other, &[attestation]).is_err());
+    let other = authority.timestamp(&other).unwrap();
+    assert!(policy.verify(&signed, &[other]).is_err());
+}
@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+
+use fixed::types::U0F32;
+use ipi::{
+    ndarray::IxDyn,
+    value::{
+        array::Array, bytes::Bytes, chrono::DateTime, hash::Hash, text::Text,
+        unit_interval::UnitInterval, uuid::Uuid, Value, ValueType,
+    },
+};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+
+fn document() -> Value {
+    let matrix =
+        ::ipi::ndarray::ArcArray::from_shape_vec(vec![2, 3], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0])
+            .unwrap()
+            .into_dyn();
+
+    Value::Map(BTreeMap::from([
+        ("id".to_string(), Value::Uuid(Uuid::generate())),
+        ("created_date".to_string(), Value::DateTime(DateTime::now())),
+        (
+            "content".to_string(),
+            Value::Hash(Hash::with_str("hello world")),
+        ),
+        ("weight".to_string(), Value::UnitInterval(0.25.into())),
+        (
+            "tags".to_string(),
+            Value::List(vec![
+                Value::Text(Text::with_en_us("foo")),
+                Value::Bytes(Bytes(vec![1, 2, 3])),
+                Value::List(vec![Value::None, Value::Bool(true), Value::I64(-42)]),
+            ]),
+        ),
+        ("matrix".to_string(), Value::ArrayF64(Array(matrix))),
+        ("empty".to_string(), Value::Map(Default::default())),
+    ]))
+}
+
+#[test]
+fn value_nested_rkyv() {
+    let value = document();
+
+    // serialize
+    let bytes = ::rkyv::to_bytes::<_, 4096>(&value).unwrap();
+
+    // validate
+    let archived = ::rkyv::check_archived_root::<Value>(&bytes[..]).unwrap();
+    assert_eq!(archived, &value);
+
+    // deserialize
+    let deserialized: Value = ::rkyv::from_bytes(&bytes).unwrap();
+    assert_eq!(deserialized, value);
+}
+
+#[test]
+fn value_array_layout() {
+    // slice the matrix with a step, which is no longer in the standard layout
+    let matrix = ::ipi::ndarray::ArcArray::from_shape_vec(vec![2, 4], (0..8).collect())
+        .unwrap()
+        .slice_move(::ipi::ndarray::s![.., ..;2])
+        .into_dyn();
+    let value = Value::ArrayI64(Array(matrix));
+
+    let bytes = ::rkyv::to_bytes::<_, 256>(&value).unwrap();
+    let archived = ::rkyv::check_archived_root::<Value>(&bytes[..]).unwrap();
+    assert_eq!(archived, &value);
+
+    let deserialized: Value = ::rkyv::from_bytes(&bytes).unwrap();
+    assert_eq!(deserialized, value);
+}
+
+#[test]
+fn value_nested_malformed() {
+    let value = Value::List(vec![Value::List(vec![Value::Bool(true)])]);
+    let mut bytes = ::rkyv::to_bytes::<_, 256>(&value).unwrap();
+
+    // corrupt the innermost bool, following the tag of `Value::Bool`
+    let index = bytes.windows(2).position(|bytes| bytes == [2, 1]).unwrap();
+    bytes[index + 1] = 2;
+    assert!(::rkyv::check_archived_root::<Value>(&bytes[..]).is_err());
+}
+
+#[test]
+fn value_nested_to_string() {
+    let value = Value::Map(BTreeMap::from([(
+        "list".to_string(),
+        Value::List(vec![Value::U8(1), Value::Text(Text::with_en_us("foo"))]),
+    )]));
+    assert_eq!(value.to_string(), r#"{"list": [1, foo]}"#);
+}
+
+#[test]
+fn value_parse() {
+    for (s, ty, expected) in [
+        ("()", ValueType::None, Value::None),
+        ("true", ValueType::Bool, Value::Bool(true)),
+        (" -42 ", ValueType::I8, Value::I8(-42)),
+        ("65535", ValueType::U16, Value::U16(65_535)),
+        ("1.5", ValueType::F64, Value::F64(1.5)),
+        (
+            "StV1DL6CwTryKyV",
+            ValueType::Bytes,
+            Value::Bytes(Bytes(b"hello world".to_vec())),
+        ),
+        (
+            "hello",
+            ValueType::Text,
+            Value::Text(Text::with_en_us("hello")),
+        ),
+        (
+            "0.25",
+            ValueType::UnitInterval,
+            Value::UnitInterval(0.25.into()),
+        ),
+        // inferred
+        ("()", ValueType::Dyn, Value::None),
+        ("false", ValueType::Dyn, Value::Bool(false)),
+        ("42", ValueType::Dyn, Value::I64(42)),
+        ("4.2", ValueType::Dyn, Value::F64(4.2)),
+        ("foo", ValueType::Dyn, Value::Text(Text::with_en_us("foo"))),
+    ] {
+        let value = Value::parse(s, ty).unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(value.value_type(), expected.value_type());
+    }
+
+    // round-trip with ToString
+    for value in [
+        Value::Hash(Hash::with_str("hello world")),
+        Value::DateTime(DateTime::now()),
+        Value::Uuid(Uuid::generate()),
+        Value::Bytes(Bytes(vec![0, 1, 2, 255])),
+    ] {
+        let parsed = Value::parse(&value.to_string(), value.value_type()).unwrap();
+        assert_eq!(parsed, value);
+    }
+
+    // reject the malformed strings
+    for (s, ty) in [
+        ("256", ValueType::U8),
+        ("-1", ValueType::U64),
+        ("yes", ValueType::Bool),
+        ("0OIl", ValueType::Bytes),
+        ("1.0", ValueType::UnitInterval),
+        ("[]", ValueType::List),
+    ] {
+        assert!(Value::parse(s, ty).is_err());
+    }
+}
+
+#[test]
+fn value_cast() {
+    for (value, ty, expected) in [
+        // integer widening and narrowing
+        (Value::I8(-1), ValueType::I64, Value::I64(-1)),
+        (Value::U64(255), ValueType::U8, Value::U8(255)),
+        (Value::I32(-7), ValueType::F32, Value::F32(-7.0)),
+        (Value::Bool(true), ValueType::U32, Value::U32(1)),
+        (Value::U8(0), ValueType::Bool, Value::Bool(false)),
+        // float to integer, truncating toward zero
+        (Value::F64(-2.9), ValueType::I16, Value::I16(-2)),
+        // float narrowing, keeping the non-finite values
+        (Value::F64(0.25), ValueType::F32, Value::F32(0.25)),
+        (
+            Value::F64(f64::NEG_INFINITY),
+            ValueType::F32,
+            Value::F32(f32::NEG_INFINITY),
+        ),
+        (
+            Value::U64(u64::MAX),
+            ValueType::F32,
+            Value::F32(u64::MAX as f32),
+        ),
+        (
+            Value::F32(0.5),
+            ValueType::UnitInterval,
+            Value::UnitInterval(0.5.into()),
+        ),
+        (
+            Value::UnitInterval(0.5.into()),
+            ValueType::F64,
+            Value::F64(0.5),
+        ),
+        // text
+        (
+            Value::Text(Text::with_en_us("foo")),
+            ValueType::Bytes,
+            Value::Bytes(Bytes(b"foo".to_vec())),
+        ),
+        (
+            Value::Bytes(Bytes(b"foo".to_vec())),
+            ValueType::Text,
+            Value::Text(Text::with_en_us("foo")),
+        ),
+        (
+            Value::Text(Text::with_en_us("42")),
+            ValueType::U8,
+            Value::U8(42),
+        ),
+        (
+            Value::I64(-42),
+            ValueType::Text,
+            Value::Text(Text::with_en_us("-42")),
+        ),
+        // dynamic
+        (Value::U8(1), ValueType::Dyn, Value::U8(1)),
+    ] {
+        assert_eq!(value.cast(ty).unwrap(), expected);
+    }
+
+    for (value, ty) in [
+        (Value::I16(256), ValueType::U8),
+        (Value::I8(-1), ValueType::U64),
+        (Value::U64(u64::MAX), ValueType::I64),
+        (Value::U8(2), ValueType::Bool),
+        (Value::F64(f64::NAN), ValueType::I32),
+        (Value::F64(1e20), ValueType::I64),
+        (Value::F64(1e39), ValueType::F32),
+        (Value::F64(-f64::MAX), ValueType::F32),
+        (Value::F64(-0.5), ValueType::UnitInterval),
+        (Value::Bytes(Bytes(vec![0xff])), ValueType::Text),
+        (Value::Hash(Hash::with_str("hello world")), ValueType::U8),
+        (Value::List(vec![]), ValueType::Text),
+    ] {
+        assert!(value.clone().cast(ty).is_err(), "{value:?} as {ty:?}");
+    }
+}
+
+#[test]
+fn value_json() {
+    let value = Value::Map(BTreeMap::from([
+        ("count".to_string(), Value::U64(5)),
+        ("ratio".to_string(), Value::F32(0.5)),
+        ("name".to_string(), Value::Text(Text::with_en_us("foo"))),
+        (
+            "tags".to_string(),
+            Value::List(vec![Value::Bool(true), Value::None]),
+        ),
+    ]));
+
+    // natural
+    let json = ::serde_json::json!({
+        "count": 5,
+        "ratio": 0.5,
+        "name": "foo",
+        "tags": [true, null],
+    });
+    assert_eq!(value.to_json(), json);
+    assert_eq!(
+        Value::from_json(&json).unwrap(),
+        Value::Map(BTreeMap::from([
+            ("count".to_string(), Value::I64(5)),
+            ("ratio".to_string(), Value::F64(0.5)),
+            ("name".to_string(), Value::Text(Text::with_en_us("foo"))),
+            (
+                "tags".to_string(),
+                Value::List(vec![Value::Bool(true), Value::None])
+            ),
+        ])),
+    );
+
+    // annotated
+    let json = value.to_json_annotated();
+    assert_eq!(
+        json["value"]["count"],
+        ::serde_json::json!({ "type": "U64", "value": 5 }),
+    );
+    assert_eq!(Value::from_json_annotated(&json).unwrap(), value);
+
+    // nested arrays
+    let matrix = ::ipi::ndarray::ArcArray::from_shape_vec(vec![2, 2], vec![1u8, 2, 3, 4])
+        .unwrap()
+        .into_dyn();
+    let value = Value::ArrayU8(Array(matrix));
+    assert_eq!(value.to_json(), ::serde_json::json!([[1, 2], [3, 4]]));
+    assert_eq!(
+        value.to_json_annotated(),
+        ::serde_json::json!({ "type": "ArrayU8", "shape": [2, 2], "value": [1, 2, 3, 4] }),
+    );
+
+    // non-finite floats
+    for value in [f64::INFINITY, f64::NEG_INFINITY] {
+        let json = Value::F64(value).to_json_annotated();
+        assert_eq!(
+            Value::from_json_annotated(&json).unwrap(),
+            Value::F64(value)
+        );
+    }
+    let json = Value::F32(f32::NAN).to_json_annotated();
+    assert!(
+        matches!(Value::from_json_annotated(&json).unwrap(), Value::F32(value) if value.is_nan())
+    );
+
+    // reject the malformed annotations
+    for json in [
+        ::serde_json::json!(5),
+        ::serde_json::json!({ "type": "U8", "value": 256 }),
+        ::serde_json::json!({ "type": "I32" }),
+        ::serde_json::json!({ "type": "Unknown", "value": 1 }),
+        ::serde_json::json!({ "type": "ArrayU8", "shape": [3], "value": [1, 2] }),
+    ] {
+        assert!(Value::from_json_annotated(&json).is_err(), "{json}");
+    }
+}
+
+/// Generates a random value of the given depth, without NaN.
+fn random_value(rng: &mut impl Rng, depth: u32) -> Value {
+    fn random_array<A>(rng: &mut impl Rng, f: impl Fn(&mut dyn RngCore) -> A) -> Array<A, IxDyn> {
+        let shape: Vec<usize> = (0..rng.gen_range(0..=3))
+            .map(|_| rng.gen_range(0..=3))
+            .collect();
+        let data = (0..shape.iter().product())
+            .map(|_| f(rng as &mut dyn RngCore))
+            .collect();
+        Array(::ipi::ndarray::ArcArray::from_shape_vec(IxDyn(&shape), data).unwrap())
+    }
+
+    let num_types = if depth == 0 { 19 } else { 25 };
+    match rng.gen_range(0..num_types) {
+        0 => Value::None,
+        1 => Value::Dyn,
+        2 => Value::Bool(rng.gen()),
+        3 => Value::I8(rng.gen()),
+        4 => Value::I16(rng.gen()),
+        5 => Value::I32(rng.gen()),
+        6 => Value::I64(rng.gen()),
+        7 => Value::U8(rng.gen()),
+        8 => Value::U16(rng.gen()),
+        9 => Value::U32(rng.gen()),
+        10 => Value::U64(rng.gen()),
+        11 => Value::F32(rng.gen::<f32>() * 1e10 - 5e9),
+        12 => Value::F64(
+            // cover the whole exponent range, except for the non-finite values
+            ::std::iter::repeat_with(|| f64::from_bits(rng.gen()))
+                .find(|value| value.is_finite())
+                .unwrap(),
+        ),
+        13 => Value::Bytes(Bytes(
+            (0..rng.gen_range(0..16)).map(|_| rng.gen()).collect(),
+        )),
+        14 => Value::Text(Text {
+            msg: (0..rng.gen_range(0..8))
+                .map(|_| rng.gen::<char>())
+                .collect(),
+            lang: ["en-us", "ko", "fr-CA"][rng.gen_range(0..3)]
+                .parse()
+                .unwrap(),
+        }),
+        15 => Value::Hash(Hash::with_bytes(&rng.gen::<[u8; 8]>())),
+        16 => Value::DateTime(DateTime(
+            ::ipi::chrono::DateTime::from_timestamp(
+                rng.gen_range(0..4_000_000_000),
+                rng.gen_range(0..1_000_000_000),
+            )
+            .unwrap(),
+        )),
+        17 => Value::Uuid(Uuid(::ipi::uuid::Uuid::from_u128(rng.gen()))),
+        18 => Value::UnitInterval(UnitInterval(U0F32::from_bits(rng.gen()))),
+        19 => Value::ArrayU8(random_array(rng, |rng| rng.gen())),
+        20 => Value::ArrayI64(random_array(rng, |rng| rng.gen())),
+        21 => Value::ArrayF32(random_array(rng, |rng| rng.gen())),
+        22 => Value::ArrayF64(random_array(rng, |rng| rng.gen::<f64>() - 0.5)),
+        23 => Value::List(
+            (0..rng.gen_range(0..4))
+                .map(|_| random_value(rng, depth - 1))
+                .collect(),
+        ),
+        _ => Value::Map(
+            (0..rng.gen_range(0..4))
+                .map(|index| (format!("key{index}"), random_value(rng, depth - 1)))
+                .collect(),
+        ),
+    }
+}
+
+#[test]
+fn value_json_round_trip() {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    for _ in 0..1_000 {
+        let value = random_value(&mut rng, 3);
+
+        // annotated JSON preserves the exact value
+        let json = value.to_json_annotated();
+        assert_eq!(Value::from_json_annotated(&json).unwrap(), value);
+
+        // also through the text
+        let text = ::serde_json::to_string(&json).unwrap();
+        let json: ::serde_json::Value = ::serde_json::from_str(&text).unwrap();
+        assert_eq!(Value::from_json_annotated(&json).unwrap(), value, "{text}");
+
+        // natural JSON is stable after the first conversion
+        let natural = Value::from_json(&value.to_json()).unwrap();
+        assert_eq!(Value::from_json(&natural.to_json()).unwrap(), natural);
+    }
+}
+
+#[test]
+fn value_ipld_round_trip() {
+    use ipi::ipld::{FromIpld, ToIpld};
+
+    let mut rng = StdRng::seed_from_u64(42);
+
+    for _ in 0..1_000 {
+        let value = random_value(&mut rng, 3);
+
+        let bytes = value.to_dag_cbor().unwrap();
+        assert_eq!(Value::from_dag_cbor(&bytes).unwrap(), value);
+
+        let json = value.to_dag_json().unwrap();
+        assert_eq!(Value::from_dag_json(&json).unwrap(), value, "{json}");
+    }
+}